@@ -0,0 +1,530 @@
+use super::*;
+
+use std::collections::{ HashMap, HashSet, };
+
+const INDENT: &str = "  ";
+
+struct Comment {
+  line:     usize,
+  end:      usize,
+  trailing: bool, // shares its line with code
+  text:     String,
+}
+
+
+
+pub struct Formatter {
+  comments: Vec<Comment>,
+  blanks:   HashSet<usize>,
+  closers:  HashMap<(usize, usize), usize>, // `{` position to line of its `}`
+
+  output: String,
+  indent: usize,
+  line:   usize, // last source line that made it into the output
+  fresh:  bool,  // nothing written since the file or block began
+}
+
+impl Formatter {
+  pub fn new(syntax: &SyntaxNode) -> Self {
+    let mut comments = Vec::new();
+    let mut blanks   = HashSet::new();
+    let mut closers  = HashMap::new();
+
+    let mut has_code    = false;
+    let mut has_comment = false;
+
+    for token in syntax.tokens() {
+      match token.token_type {
+        TokenType::EOL => {
+          if !has_code && !has_comment {
+            blanks.insert(token.line.0);
+          }
+
+          has_code    = false;
+          has_comment = false;
+        },
+
+        TokenType::Comment => {
+          let text = if token.raw.starts_with("---") {
+            token.raw.clone()
+          } else {
+            token.raw.trim_end().to_string()
+          };
+
+          comments.push(
+            Comment {
+              line:     token.line.0,
+              end:      token.line.0 + token.raw.matches('\n').count(),
+              trailing: has_code,
+              text,
+            }
+          );
+
+          has_comment = true
+        },
+
+        TokenType::Whitespace => (),
+
+        _ => has_code = true,
+      }
+    }
+
+    Self::find_closers(syntax, &mut closers);
+
+    Formatter {
+      comments,
+      blanks,
+      closers,
+
+      output: String::new(),
+      indent: 0,
+      line:   0,
+      fresh:  true,
+    }
+  }
+
+  fn find_closers(node: &SyntaxNode, closers: &mut HashMap<(usize, usize), usize>) {
+    if node.kind == SyntaxKind::Group {
+      if let (Some(&SyntaxElement::Token(ref open)), Some(&SyntaxElement::Token(ref close))) = (node.children.first(), node.children.last()) {
+        if open.lexeme == "{" && close.lexeme == "}" {
+          closers.insert((open.line.0, open.slice.0), close.line.0);
+        }
+      }
+    }
+
+    for child in &node.children {
+      if let SyntaxElement::Node(ref child) = *child {
+        Self::find_closers(child, closers)
+      }
+    }
+  }
+
+
+
+  pub fn format(mut self, ast: &[Statement]) -> String {
+    self.statements(ast, None);
+
+    self.flush_comments(usize::max_value());
+
+    if !self.output.is_empty() {
+      self.output.push('\n')
+    }
+
+    self.output
+  }
+
+  fn statements(&mut self, statements: &[Statement], end: Option<usize>) {
+    for statement in statements {
+      let line = statement.pos.line();
+
+      self.flush_comments(line);
+
+      self.begin_line(line);
+      self.statement(statement);
+
+      self.flush_trailing(line);
+    }
+
+    if let Some(end) = end {
+      self.flush_comments(end);
+    }
+  }
+
+  fn statement(&mut self, statement: &Statement) {
+    use self::StatementNode::*;
+
+    match statement.node {
+      Expression(ref expression) => self.expression(expression),
+
+      Variable(ref t, ref left, ref right) => {
+        self.list(left);
+
+        match (t, right) {
+          (&Type::Nil, &Some(ref right)) => {
+            self.write(" := ");
+            self.expression(right)
+          },
+
+          (t, &Some(ref right)) => {
+            self.write(&format!(": {} = ", t));
+            self.expression(right)
+          },
+
+          (t, &None) => self.write(&format!(": {}", t)),
+        }
+      },
+
+      Constant(ref t, ref left, ref right) => {
+        self.list(left);
+
+        if *t == Type::Nil {
+          self.write(" :: ")
+        } else {
+          self.write(&format!(": {} : ", t))
+        }
+
+        self.expression(right)
+      },
+
+      Assignment(ref left, ref right) => {
+        self.expression(left);
+        self.write(" = ");
+        self.expression(right)
+      },
+
+      For(ref binding, ref iterable, ref body) => {
+        self.write("for ");
+        self.expression(binding);
+        self.write(" in ");
+        self.expression(iterable);
+        self.write(" ");
+        self.expression(body)
+      },
+
+      Function(ref name, ref generics, ref params, ref return_type, ref body) => {
+        self.write(&format!("fun {}{}({}) ", name, Self::generics(generics), Self::params(params)));
+
+        if *return_type != Type::Nil {
+          self.write(&format!("-> {} ", return_type))
+        }
+
+        self.expression(body)
+      },
+
+      Signature(ref name, ref params, ref return_type) => {
+        self.write(&format!("fun {}({})", name, Self::params(params)));
+
+        if *return_type != Type::Nil {
+          self.write(&format!(" -> {}", return_type))
+        }
+      },
+
+      Trait(ref name, ref body) => {
+        self.write(&format!("trait {} ", name));
+        self.expression(body)
+      },
+
+      Implement(ref trait_name, ref t, ref body) => {
+        match *trait_name {
+          Some(ref trait_name) => self.write(&format!("implement {} for {} ", trait_name, t)),
+          None                 => self.write(&format!("implement {} ", t)),
+        }
+
+        self.expression(body)
+      },
+
+      Struct(ref name, ref generics, ref body) => {
+        self.write(&format!("struct {}{} ", name, Self::generics(generics)));
+        self.expression(body)
+      },
+
+      Alias(ref name, ref t) => self.write(&format!("type {} = {}", name, t)),
+
+      Return(ref value) => {
+        self.write("return");
+
+        if let Some(ref value) = *value {
+          self.write(" ");
+          self.list(value)
+        }
+      },
+    }
+  }
+
+  // tuples on the left of `:=` and after `return` go without parentheses
+  fn list(&mut self, expression: &Expression) {
+    if let ExpressionNode::Tuple(ref elements) = expression.node {
+      self.expressions(elements)
+    } else {
+      self.expression(expression)
+    }
+  }
+
+  fn expressions(&mut self, expressions: &[Expression]) {
+    for (i, expression) in expressions.iter().enumerate() {
+      if i > 0 {
+        self.write(", ")
+      }
+
+      self.expression(expression)
+    }
+  }
+
+  // parentheses only where the parser would group differently without them
+  fn operand(&mut self, operand: &Expression, parent: &Operator, right: bool) {
+    let grouped = match operand.node {
+      ExpressionNode::Binary(_, ref op, _) => {
+        op.precedence() < parent.precedence() || (op.precedence() == parent.precedence() && right != parent.is_right_associative())
+      },
+
+      ExpressionNode::Range(..) => true,
+
+      _ => false,
+    };
+
+    if grouped {
+      self.write("(");
+      self.expression(operand);
+      self.write(")")
+    } else {
+      self.expression(operand)
+    }
+  }
+
+  fn expression(&mut self, expression: &Expression) {
+    use self::ExpressionNode::*;
+
+    match expression.node {
+      Int(ref n)        => self.write(&n.to_string()),
+      Float(ref n)      => self.write(&Self::float(*n)),
+      String(ref s)     => self.write(&format!("\"{}\"", Self::escape(s, '"'))),
+      Char(ref c)       => self.write(&format!("'{}'", Self::escape(&c.to_string(), '\''))),
+      Bool(ref b)       => self.write(&b.to_string()),
+      Nil               => self.write("nil"),
+      Identifier(ref n) => self.write(n),
+
+      Binary(ref left, ref op, ref right) => {
+        self.operand(left, op, false);
+        self.write(&format!(" {} ", op));
+        self.operand(right, op, true)
+      },
+
+      Unary(ref op, ref operand) => {
+        self.write(op.as_str());
+
+        // `--` would start a comment
+        match operand.node {
+          Binary(..) | Range(..) | Unary(..) => {
+            self.write("(");
+            self.expression(operand);
+            self.write(")")
+          },
+
+          _ => self.expression(operand),
+        }
+      },
+
+      Tuple(ref elements) => {
+        self.write("(");
+        self.expressions(elements);
+        self.write(")")
+      },
+
+      List(ref elements) => {
+        self.write("[");
+        self.expressions(elements);
+        self.write("]")
+      },
+
+      Fields(ref names) => {
+        self.write("{ ");
+        self.expressions(names);
+        self.write(" }")
+      },
+
+      Field(ref value, ref name) => {
+        match value.node {
+          Binary(..) | Range(..) | Unary(..) | Cast(..) => {
+            self.write("(");
+            self.expression(value);
+            self.write(")")
+          },
+
+          _ => self.expression(value),
+        }
+
+        self.write(&format!(".{}", name))
+      },
+
+      Cast(ref value, ref t) => {
+        match value.node {
+          Binary(..) | Range(..) => {
+            self.write("(");
+            self.expression(value);
+            self.write(")")
+          },
+
+          _ => self.expression(value),
+        }
+
+        self.write(&format!(" as {}", t))
+      },
+
+      Call(ref callee, ref arguments) => {
+        self.expression(callee);
+        self.write("(");
+        self.expressions(arguments);
+        self.write(")")
+      },
+
+      If(ref condition, ref body, ref otherwise) => {
+        self.write("if ");
+        self.expression(condition);
+        self.write(" ");
+        self.expression(body);
+
+        if let Some(ref otherwise) = *otherwise {
+          self.write(" else ");
+          self.expression(otherwise)
+        }
+      },
+
+      Range(ref start, ref end, inclusive, ref step) => {
+        self.expression(start);
+        self.write(if inclusive { "..=" } else { ".." });
+        self.expression(end);
+
+        if let Some(ref step) = *step {
+          self.write(" by ");
+          self.expression(step)
+        }
+      },
+
+      Block(ref statements) => {
+        let line = expression.pos.line();
+        let end  = if let TokenElement::Pos(_, slice) = expression.pos {
+          self.closers.get(&(line, slice.0)).cloned()
+        } else {
+          None
+        };
+
+        // one-line blocks holding a lone expression stay on one line
+        if end == Some(line) && statements.len() == 1 && !self.comments.iter().any(|c| c.line == line) {
+          if let StatementNode::Expression(ref expression) = statements[0].node {
+            self.write("{ ");
+            self.expression(expression);
+            self.write(" }");
+
+            return
+          }
+        }
+
+        self.write("{");
+        self.flush_trailing(line);
+
+        self.indent += 1;
+        self.fresh   = true;
+
+        self.statements(statements, end);
+
+        self.indent -= 1;
+
+        // empty blocks stay on one line, blank lines before `}` are dropped
+        if !self.fresh {
+          self.output.push('\n');
+
+          for _ in 0 .. self.indent {
+            self.output.push_str(INDENT)
+          }
+        }
+
+        self.write("}");
+
+        self.fresh = false;
+        self.line  = end.unwrap_or(self.line)
+      },
+
+      EOF => (),
+    }
+  }
+
+
+
+  // standalone comments before `line` get their own lines, trailing ones stick to the last line
+  fn flush_comments(&mut self, line: usize) {
+    while !self.comments.is_empty() && self.comments[0].line < line {
+      let comment = self.comments.remove(0);
+
+      if comment.trailing && !self.fresh {
+        self.write(&format!(" {}", comment.text))
+      } else {
+        self.begin_line(comment.line);
+        self.write(&comment.text)
+      }
+
+      self.line = comment.end
+    }
+  }
+
+  fn flush_trailing(&mut self, line: usize) {
+    while !self.comments.is_empty() && self.comments[0].line == line && self.comments[0].trailing {
+      let comment = self.comments.remove(0);
+
+      self.write(&format!(" {}", comment.text));
+      self.line = comment.end
+    }
+  }
+
+  fn begin_line(&mut self, line: usize) {
+    if !self.output.is_empty() {
+      let gap = (self.line + 1 .. line).any(|l| self.blanks.contains(&l));
+
+      self.output.push('\n');
+
+      if gap && !self.fresh {
+        self.output.push('\n')
+      }
+    }
+
+    for _ in 0 .. self.indent {
+      self.output.push_str(INDENT)
+    }
+
+    if line > self.line {
+      self.line = line
+    }
+
+    self.fresh = false
+  }
+
+  fn write(&mut self, text: &str) {
+    self.output.push_str(text)
+  }
+
+
+
+  // floats keep their decimal point, so they read back as floats
+  fn float(n: f64) -> String {
+    let literal = n.to_string();
+
+    if literal.contains('.') || !n.is_finite() {
+      literal
+    } else {
+      format!("{}.0", literal)
+    }
+  }
+
+  // `self` goes without a type
+  fn params(params: &[(String, Type)]) -> String {
+    params.iter().map(|&(ref param, ref t)| if *t == Type::Nil {
+      param.clone()
+    } else {
+      format!("{}: {}", param, t)
+    }).collect::<Vec<_>>().join(", ")
+  }
+
+  fn generics(generics: &[String]) -> String {
+    if generics.is_empty() {
+      String::new()
+    } else {
+      format!("<{}>", generics.join(", "))
+    }
+  }
+
+  fn escape(s: &str, delimeter: char) -> String {
+    let mut escaped = String::new();
+
+    for c in s.chars() {
+      match c {
+        '\\'                => escaped.push_str("\\\\"),
+        '\n'                => escaped.push_str("\\n"),
+        '\r'                => escaped.push_str("\\r"),
+        '\t'                => escaped.push_str("\\t"),
+        c if c == delimeter => { escaped.push('\\'); escaped.push(c) },
+        c                   => escaped.push(c),
+      }
+    }
+
+    escaped
+  }
+}
@@ -0,0 +1,10 @@
+pub mod formatter;
+
+pub use self::formatter::*;
+
+#[cfg(test)]
+mod tests;
+
+use super::lexer::{ TokenElement, TokenType, };
+use super::parser::*;
+use super::visitor::Type;
\ No newline at end of file
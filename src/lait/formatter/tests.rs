@@ -0,0 +1,74 @@
+use super::*;
+
+use std::env;
+use std::fs;
+
+// the formatted text and the s-expression of what it parses to
+fn format(content: &str) -> (String, String) {
+  ::parse("test.lait", content, |_, syntax, ast| (Formatter::new(syntax).format(&ast), ast.to_sexp())).expect("source doesn't parse")
+}
+
+// formatting keeps the meaning and doesn't change formatted code
+fn assert_round_trip(content: &str) {
+  let (formatted, before) = format(content);
+  let (again, after)      = format(&formatted);
+
+  assert_eq!(before, after, "formatting changed the tree of:\n{}", content);
+  assert_eq!(formatted, again, "formatting isn't idempotent for:\n{}", content)
+}
+
+#[test]
+fn comments() {
+  assert_round_trip("-- leading\nhp := 10   -- trailing\n---\nblock\n---\n\n\n\nmp := 2\n-- last\n")
+}
+
+#[test]
+fn blank_lines() {
+  assert_round_trip("a := 1\n\n\n\nb := 2\nc := 3\n\nfun f() {\n\n  d := 4\n\n\n  e := 5\n\n}\n")
+}
+
+#[test]
+fn blocks() {
+  assert_round_trip("fun f(n: int) -> int {\nif n > 1 { return n } else {\nreturn 1\n}\n}\nfor i in 0 .. 10 by 2 {\n}\nx := if true { 1 } else { 2 }\n")
+}
+
+#[test]
+fn casts() {
+  assert_round_trip("hp := 10\nf := (hp + 1) as float\nc := 'a' as int + 1\nd := -hp as f32\ne := (hp as float).x\n");
+  assert_round_trip("hp := 10\nlow := hp as int < 5\nif hp as float < 5.0 {\n  hp = 1\n}\n")
+}
+
+#[test]
+fn tuples() {
+  assert_round_trip("fun pos() -> (int, str) {\n  return 1, \"a\"\n}\nx, y := pos()\nz: (int, str) = (x, y)\n[a, b] := [1, 2]\n")
+}
+
+#[test]
+fn precedence() {
+  assert_round_trip("n := 10 - (5 - 2)\nm := (1 + 2) * 3\np := (2 ^ 3) ^ 2\nq := 2 ^ 3 ^ 2\nr := -(1 + 2)\ns := 1 + 2 * 3 == 7\nt := (1 .. 3)\n")
+}
+
+#[test]
+fn declarations() {
+  assert_round_trip("struct Pool<T> {\n  items: [T]\n}\ntrait Draw {\n  fun draw(self) -> int\n}\nimplement Draw for Pool<int> {\n  fun draw(self) -> int { return 1 }\n}\ntype hp = int\nk :: 5\nscale: f32 : 2\nname: str? = nil\n")
+}
+
+#[test]
+fn check_leaves_files_alone() {
+  let path = env::temp_dir().join(format!("lait-fmt-check-{}.lait", ::std::process::id()));
+  let path = path.to_str().unwrap();
+
+  let messy = "a   :=  1\n";
+
+  fs::write(path, messy).unwrap();
+
+  assert!(!::format(path, true), "`--check` passed an unformatted file");
+  assert_eq!(fs::read_to_string(path).unwrap(), messy);
+
+  assert!(::format(path, false));
+  assert_eq!(fs::read_to_string(path).unwrap(), "a := 1\n");
+
+  assert!(::format(path, true), "`--check` failed a formatted file");
+
+  fs::remove_file(path).unwrap()
+}
@@ -8,6 +8,7 @@ pub struct Lexer<'l> {
   tokenizer: Tokenizer<'l>,
   matchers:  Vec<Rc<Matcher<'l>>>,
   source:    &'l Source,
+  trivia:    bool,
 }
 
 impl<'l> Lexer<'l> {
@@ -16,6 +17,7 @@ impl<'l> Lexer<'l> {
       tokenizer,
       matchers: Vec::new(),
       source,
+      trivia:   false,
     }
   }
 
@@ -34,7 +36,7 @@ impl<'l> Lexer<'l> {
     lexer.matchers.push(
       Rc::new(
         KeyMatcher::new(Keyword, &[
-          "fun", "->"
+          "fun", "->", "return", "struct", "trait", "implement", "type", "nil", "as", "for", "in", "by", "if", "else"
         ])
       )
     );
@@ -50,16 +52,31 @@ impl<'l> Lexer<'l> {
 
     lexer.matchers.push(
       Rc::new(
-        ConstantStringMatcher::new(Operator, &["+", "-", "*", "/", "<", ">", "==", "!=", "<=", ">="])
+        ConstantStringMatcher::new(Operator, &["++", "==", "!=", "<=", ">=", "+", "-", "*", "/", "%", "^", "<", ">"])
       )
     );
 
     lexer.matchers.push(
       Rc::new(
-        ConstantCharMatcher::new(Symbol, &['(', ')', '[', ']', '{', '}', ',', ':', ';', '=', '.', '|'])
+        ConstantStringMatcher::new(Symbol, &["..=", ".."])
       )
     );
 
+    lexer.matchers.push(
+      Rc::new(
+        ConstantCharMatcher::new(Symbol, &['(', ')', '[', ']', '{', '}', ',', ':', ';', '=', '.', '|', '?'])
+      )
+    );
+
+    lexer
+  }
+
+  // keeps whitespace and comments, so the tokens add up to the original text
+  pub fn lossless(data: Vec<char>, source: &'l Source) -> Self {
+    let mut lexer = Self::default(data, source);
+
+    lexer.trivia = true;
+
     lexer
   }
 
@@ -104,9 +121,9 @@ impl<'l> Iterator for Lexer<'l> {
     };
 
     match token.token_type {
-      TokenType::EOF        => None,
-      TokenType::Whitespace => self.next(),
-      _                     => Some(Ok(token)),
+      TokenType::EOF                         => None,
+      ref t if t.is_trivia() && !self.trivia => self.next(),
+      _                                      => Some(Ok(token)),
     }
   }
 }
\ No newline at end of file
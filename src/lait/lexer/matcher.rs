@@ -36,26 +36,42 @@ pub struct CommentMatcher;
 
 impl<'t> Matcher<'t> for CommentMatcher {
   fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token<'t>>, ()> {
+    let mut accum = String::new();
+
     if tokenizer.peek_range(3).unwrap_or_else(String::new) == "---" {
       tokenizer.advance_n(3);
+      accum.push_str("---");
 
       while !tokenizer.end() {
         if tokenizer.peek_range(3).unwrap_or_else(String::new) == "---" {
           tokenizer.advance_n(3);
+          accum.push_str("---");
           break
         }
 
-        tokenizer.advance()
+        let c = tokenizer.peek().unwrap();
+
+        // block comments may span lines, keep the position in sync
+        if c == '\n' {
+          tokenizer.pos.0 += 1;
+          tokenizer.pos.1 = 0;
+          tokenizer.index += 1;
+        } else {
+          tokenizer.advance()
+        }
+
+        accum.push(c)
       }
 
-      Ok(Some(token!(tokenizer, EOL, "\n".into())))
+      Ok(Some(token!(tokenizer, Comment, accum)))
 
     } else if tokenizer.peek_range(2).unwrap_or_else(String::new) == "--" {
       while !tokenizer.end() && tokenizer.peek() != Some('\n') {
+        accum.push(tokenizer.peek().unwrap());
         tokenizer.advance()
       }
 
-      Ok(Some(token!(tokenizer, EOL, "\n".into())))
+      Ok(Some(token!(tokenizer, Comment, accum)))
     } else {
       Ok(None)
     }
@@ -82,7 +98,10 @@ impl<'t> Matcher<'t> for ConstantStringMatcher {
   fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token<'t>>, ()> {
     for constant in self.constants {
       let len = constant.len();
-      let c   = tokenizer.peek_range(len).unwrap();
+      let c   = match tokenizer.peek_range(len) {
+        Some(c) => c,
+        None    => continue,
+      };
 
       if c == *constant {
         tokenizer.advance_n(len);
@@ -316,7 +335,7 @@ impl<'t> Matcher<'t> for NumberLiteralMatcher {
     let curr = tokenizer.next().unwrap();
     if curr.is_digit(10) {
       accum.push(curr)
-    } else if curr == '.' {
+    } else if curr == '.' && tokenizer.peek() != Some('.') {
       accum.push_str("0.")
     } else {
       return Ok(None)
@@ -324,6 +343,12 @@ impl<'t> Matcher<'t> for NumberLiteralMatcher {
 
     while !tokenizer.end() {
       let current = tokenizer.peek().unwrap();
+
+      // `..` starts a range rather than a fraction
+      if current == '.' && tokenizer.peek_n(1) == Some('.') {
+        break
+      }
+
       if !current.is_whitespace() && current.is_digit(10) || current == '.' {
         if current == '.' && accum.contains('.') {
           let pos = tokenizer.pos;
@@ -356,10 +381,21 @@ impl<'t> Matcher<'t> for NumberLiteralMatcher {
 
         Ok(Some(token!(tokenizer, Float, accum)))
       } else {
-        let literal: String = match accum.parse::<i64>() {
-          Ok(result) => result.to_string(),
-          Err(error) => panic!("unable to parse int: {}", error)
-        };
+        // wide enough for `u64` and a negated `i64::MIN`, the types decide what fits later
+        if accum.parse::<i128>().is_err() {
+          let pos = tokenizer.pos;
+
+          return Err(
+            response!(
+              Wrong("integer literal is too large"),
+              tokenizer.source.file,
+              TokenElement::Pos(
+                (pos.0, &tokenizer.source.lines.get(pos.0.saturating_sub(1)).unwrap_or(tokenizer.source.lines.last().unwrap())),
+                ((pos.1 + 1).saturating_sub(accum.len()), pos.1),
+              )
+            )
+          )
+        }
 
         Ok(Some(token!(tokenizer, Int, accum)))
       }
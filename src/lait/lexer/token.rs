@@ -12,11 +12,22 @@ pub enum TokenType {
   Keyword,
   Symbol,
   Whitespace,
+  Comment,
   Operator,
   EOL,
   EOF,
 }
 
+impl TokenType {
+  // whitespace and comments carry no meaning for the parser
+  pub fn is_trivia(&self) -> bool {
+    match *self {
+      TokenType::Whitespace | TokenType::Comment => true,
+      _                                          => false,
+    }
+  }
+}
+
 impl fmt::Display for TokenType {
   fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
     use self::TokenType::*;
@@ -31,6 +42,7 @@ impl fmt::Display for TokenType {
       Symbol     => write!(f, "Symbol"),
       Keyword    => write!(f, "Symbol"),
       Whitespace => write!(f, "Whitespace"),
+      Comment    => write!(f, "Comment"),
       Operator   => write!(f, "Operator"),
       EOL        => write!(f, "EOL"),
       EOF        => write!(f, "EOF"),
@@ -52,6 +64,17 @@ pub enum TokenElement<'e> {
 
 use self::TokenElement::{ Row, Line, Pair, Type, Pos, Block, Ref, Lexeme, };
 
+impl<'e> TokenElement<'e> {
+  // the line a position starts on, 0 for elements that aren't positions
+  pub fn line(&self) -> usize {
+    match *self {
+      Pos(line, _) => line.0,
+      Ref(token)   => token.line.0,
+      _            => 0,
+    }
+  }
+}
+
 impl<'t> PartialEq<Token<'t>> for TokenElement<'t> {
   fn eq (&self, rhs: &Token<'t>) -> bool {
     rhs == self
@@ -166,6 +189,7 @@ pub struct Token<'t> {
   pub line:       (usize, &'t str),
   pub slice:      (usize, usize),
   pub lexeme:     String,
+  pub raw:        String, // exact source text, including delimeters
 }
 
 impl<'t> Token<'t> {
@@ -174,7 +198,8 @@ impl<'t> Token<'t> {
       token_type,
       line,
       slice,
-      lexeme: lexeme.to_string()
+      lexeme: lexeme.to_string(),
+      raw:    lexeme.to_string(),
    }
   }
 }
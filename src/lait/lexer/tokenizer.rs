@@ -112,7 +112,12 @@ impl<'t> Tokenizer<'t> {
     self.take_snapshot();
 
     match matcher.try_match(self)? {
-      Some(t) => {
+      Some(mut t) => {
+        let start = self.peek_snapshot().unwrap().index;
+        let end   = if self.index < self.items.len() { self.index } else { self.items.len() };
+
+        t.raw = self.items[start .. end].iter().collect();
+
         self.commit_snapshot();
         Ok(Some(t))
       }
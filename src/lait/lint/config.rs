@@ -0,0 +1,204 @@
+use super::super::error::Response::{ Wrong, Weird, };
+use super::super::source::FilePath;
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{ Path, PathBuf, };
+
+use super::*;
+
+pub const CONFIG_FILE: &str = "lait.lint";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Level {
+  Allow,
+  Warn,
+  Deny,
+}
+
+impl Level {
+  pub fn from_str(level: &str) -> Option<Self> {
+    match level {
+      "allow" => Some(Level::Allow),
+      "warn"  => Some(Level::Warn),
+      "deny"  => Some(Level::Deny),
+      _       => None,
+    }
+  }
+}
+
+// how loud each lint is, from the nearest `lait.lint` and then the file's own
+// `-- lait: allow(name)` comments, which cover the whole file on a line of their
+// own and only their line when trailing code
+#[derive(Debug, Clone)]
+pub struct LintConfig {
+  pub levels: HashMap<String, Level>,
+  pub lines:  HashMap<usize, HashMap<String, Level>>,
+}
+
+impl LintConfig {
+  pub fn new() -> Self {
+    LintConfig {
+      levels: LINTS.iter().map(|&(name, _)| (name.to_string(), Level::Warn)).collect(),
+      lines:  HashMap::new(),
+    }
+  }
+
+  pub fn load(source: &Source, directives: &[Directive]) -> Result<Self, ()> {
+    let mut config = Self::new();
+
+    if let Some(path) = Self::find(Path::new(&source.file.0)) {
+      config.read(&path)?
+    }
+
+    config.directives(source, directives)?;
+
+    Ok(config)
+  }
+
+  pub fn level(&self, lint: &str, line: usize) -> Level {
+    if let Some(level) = self.lines.get(&line).and_then(|levels| levels.get(lint)) {
+      return *level
+    }
+
+    self.levels.get(lint).cloned().unwrap_or(Level::Warn)
+  }
+
+  // the config file next to the source, or in any directory above it
+  fn find(file: &Path) -> Option<PathBuf> {
+    // a relative path's parents stop at the current directory
+    let file = match env::current_dir() {
+      Ok(current) => current.join(file),
+      Err(_)      => file.to_path_buf(),
+    };
+
+    let mut directory = file.parent();
+
+    while let Some(current) = directory {
+      let candidate = current.join(CONFIG_FILE);
+
+      if candidate.is_file() {
+        return Some(candidate)
+      }
+
+      directory = current.parent()
+    }
+
+    None
+  }
+
+  // `name = level` per line, `--` starts a comment
+  fn read(&mut self, path: &Path) -> Result<(), ()> {
+    let file = FilePath(path.display().to_string());
+
+    let content = match fs::read_to_string(path) {
+      Ok(content) => content,
+      Err(error)  => return Err(
+        response!(
+          Wrong(format!("can't read lint config, {}", error)),
+          file
+        )
+      ),
+    };
+
+    for (i, line) in content.lines().enumerate() {
+      let setting = match line.find("--") {
+        Some(start) => &line[.. start],
+        None        => line,
+      }.trim();
+
+      if setting.is_empty() {
+        continue
+      }
+
+      let mut parts = setting.splitn(2, '=').map(|part| part.trim());
+
+      let (name, level) = match (parts.next(), parts.next()) {
+        (Some(name), Some(level)) => (name, level),
+
+        _ => return Err(
+          response!(
+            Wrong("expected a lint setting like `naming = allow`"),
+            file,
+            TokenElement::Line((i + 1, line))
+          )
+        ),
+      };
+
+      let level = self.parse_level(level, &file, i + 1, line)?;
+
+      if self.known(name, &file, i + 1, line) {
+        self.levels.insert(name.to_string(), level);
+      }
+    }
+
+    Ok(())
+  }
+
+  pub fn directives(&mut self, source: &Source, directives: &[Directive]) -> Result<(), ()> {
+    for directive in directives {
+      let (line, text) = (directive.line, &source.lines[directive.line - 1]);
+
+      // `strict` is for the checker
+      if directive.text == "strict" {
+        continue
+      }
+
+      let open = match directive.text.find('(') {
+        Some(open) if directive.text.ends_with(')') => open,
+
+        _ => return Err(
+          response!(
+            Wrong(format!("malformed directive `{}`, expected `strict` or one like `allow(naming)`", directive.text)),
+            source.file,
+            TokenElement::Line((line, text))
+          )
+        ),
+      };
+
+      let level = self.parse_level(&directive.text[.. open], &source.file, line, text)?;
+
+      for name in directive.text[open + 1 .. directive.text.len() - 1].split(',').map(|name| name.trim()) {
+        if !self.known(name, &source.file, line, text) {
+          continue
+        }
+
+        if directive.trailing {
+          self.lines.entry(line).or_insert_with(HashMap::new).insert(name.to_string(), level);
+        } else {
+          self.levels.insert(name.to_string(), level);
+        }
+      }
+    }
+
+    Ok(())
+  }
+
+  fn parse_level(&self, level: &str, file: &FilePath, line: usize, text: &str) -> Result<Level, ()> {
+    match Level::from_str(level.trim()) {
+      Some(level) => Ok(level),
+      None        => Err(
+        response!(
+          Wrong(format!("unknown lint level `{}`, expected `allow`, `warn` or `deny`", level.trim())),
+          file,
+          TokenElement::Line((line, text))
+        )
+      ),
+    }
+  }
+
+  fn known(&self, name: &str, file: &FilePath, line: usize, text: &str) -> bool {
+    if LINTS.iter().any(|&(lint, _)| lint == name) {
+      return true
+    }
+
+    response!(
+      Weird(format!("no lint named `{}`", name)),
+      file,
+      TokenElement::Line((line, text))
+    );
+
+    false
+  }
+}
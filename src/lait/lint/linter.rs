@@ -0,0 +1,250 @@
+use super::super::error::Response::{ Wrong, Weird, };
+use super::super::visitor::Type;
+
+use std::collections::HashMap;
+
+use super::*;
+
+// every lint with what it looks for, all of them warn unless configured otherwise
+pub const LINTS: &[(&str, &str)] = &[
+  ("naming",         "values and functions in snake_case, types in PascalCase"),
+  ("float_equality", "floats compared with `==` or `!=`"),
+  ("deep_nesting",   "blocks nested more than four deep within a function"),
+];
+
+const MAX_NESTING: usize = 4;
+
+// style and correctness checks over the checked tree, reported at the level
+// the config gives them, denied ones fail the file like any other error
+pub struct Linter<'l> {
+  pub source: &'l Source,
+  pub config: &'l LintConfig,
+  pub types:  &'l HashMap<*const Expression<'l>, Type>, // as the checker found them
+
+  denied: bool,
+  depth:  usize, // blocks entered since the enclosing function
+}
+
+impl<'l> Linter<'l> {
+  pub fn new(source: &'l Source, config: &'l LintConfig, types: &'l HashMap<*const Expression<'l>, Type>) -> Self {
+    Linter {
+      source,
+      config,
+      types,
+
+      denied: false,
+      depth:  0,
+    }
+  }
+
+  // whether the tree got through without a denied lint
+  pub fn lint(mut self, ast: &'l [Statement<'l>]) -> bool {
+    let _ = self.walk_statements(ast);
+
+    !self.denied
+  }
+
+  fn report(&mut self, lint: &str, message: String, pos: &TokenElement<'l>) {
+    match self.config.level(lint, pos.line()) {
+      Level::Allow => (),
+
+      Level::Warn => response!(
+        Weird(format!("{} [{}]", message, lint)),
+        self.source.file,
+        pos
+      ),
+
+      Level::Deny => {
+        self.denied = true;
+
+        response!(
+          Wrong(format!("{} [{}]", message, lint)),
+          self.source.file,
+          pos
+        )
+      },
+    }
+  }
+
+
+
+  fn value_name(&mut self, name: &str, what: &str, pos: &TokenElement<'l>) {
+    if !Self::is_snake(name) {
+      self.report("naming", format!("{} `{}` should be snake_case, e.g. `{}`", what, name, Self::to_snake(name)), pos)
+    }
+  }
+
+  fn type_name(&mut self, name: &str, what: &str, pos: &TokenElement<'l>) {
+    if !Self::is_pascal(name) {
+      self.report("naming", format!("{} `{}` should be PascalCase, e.g. `{}`", what, name, Self::to_pascal(name)), pos)
+    }
+  }
+
+  // every name a declaration pattern binds, constants may be SCREAMING_SNAKE_CASE too
+  fn pattern_names(&mut self, pattern: &'l Expression<'l>, constant: bool) {
+    use self::ExpressionNode::*;
+
+    match pattern.node {
+      Identifier(ref name) => if !(constant && Self::is_screaming(name)) {
+        self.value_name(name, if constant { "constant" } else { "variable" }, &pattern.pos)
+      },
+
+      Tuple(ref parts) | List(ref parts) | Fields(ref parts) => for part in parts {
+        self.pattern_names(part, constant)
+      },
+
+      _ => (),
+    }
+  }
+
+  fn is_snake(name: &str) -> bool {
+    name.chars().all(|c| c.is_lowercase() || c.is_ascii_digit() || c == '_')
+  }
+
+  fn is_screaming(name: &str) -> bool {
+    name.chars().all(|c| c.is_uppercase() || c.is_ascii_digit() || c == '_')
+  }
+
+  fn is_pascal(name: &str) -> bool {
+    name.chars().next().map_or(true, |c| c.is_uppercase()) && !name.contains('_')
+  }
+
+  fn to_snake(name: &str) -> String {
+    let mut snake = String::new();
+
+    for (i, c) in name.chars().enumerate() {
+      if c.is_uppercase() {
+        if i > 0 && !snake.ends_with('_') {
+          snake.push('_')
+        }
+
+        snake.extend(c.to_lowercase())
+      } else {
+        snake.push(c)
+      }
+    }
+
+    snake
+  }
+
+  fn to_pascal(name: &str) -> String {
+    name.split('_').map(|part| {
+      let mut chars = part.chars();
+
+      match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+        None        => String::new(),
+      }
+    }).collect()
+  }
+
+  fn is_float(&self, expression: &'l Expression<'l>) -> bool {
+    self.types.get(&(expression as *const _)).map_or(false, |t| t.float().is_some())
+  }
+}
+
+impl<'l> Walk<'l> for Linter<'l> {
+  fn walk_variable(&mut self, _statement: &'l Statement<'l>, _t: &'l Type, left: &'l Expression<'l>, right: Option<&'l Expression<'l>>) -> Result<(), ()> {
+    self.pattern_names(left, false);
+
+    if let Some(right) = right {
+      self.walk_expression(right)?
+    }
+
+    Ok(())
+  }
+
+  fn walk_constant(&mut self, _statement: &'l Statement<'l>, _t: &'l Type, left: &'l Expression<'l>, right: &'l Expression<'l>) -> Result<(), ()> {
+    self.pattern_names(left, true);
+    self.walk_expression(right)
+  }
+
+  fn walk_for(&mut self, _statement: &'l Statement<'l>, binding: &'l Expression<'l>, iterable: &'l Expression<'l>, body: &'l Expression<'l>) -> Result<(), ()> {
+    self.pattern_names(binding, false);
+    self.walk_expression(iterable)?;
+    self.walk_expression(body)
+  }
+
+  fn walk_function(&mut self, statement: &'l Statement<'l>, name: &'l str, generics: &'l [String], params: &'l [(String, Type)], _return_type: &'l Type, body: &'l Expression<'l>) -> Result<(), ()> {
+    // operators go by their symbol
+    if name.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+      self.value_name(name, "function", &statement.pos)
+    }
+
+    for generic in generics {
+      self.type_name(generic, "type parameter", &statement.pos)
+    }
+
+    for &(ref param, _) in params {
+      self.value_name(param, "parameter", &statement.pos)
+    }
+
+    let depth = self.depth;
+
+    self.depth = 0;
+
+    // the body itself doesn't count as nesting
+    let result = match body.node {
+      ExpressionNode::Block(ref statements) => self.walk_statements(statements),
+      _                                     => self.walk_expression(body),
+    };
+
+    self.depth = depth;
+
+    result
+  }
+
+  fn walk_struct(&mut self, statement: &'l Statement<'l>, name: &'l str, generics: &'l [String], body: &'l Expression<'l>) -> Result<(), ()> {
+    self.type_name(name, "struct", &statement.pos);
+
+    for generic in generics {
+      self.type_name(generic, "type parameter", &statement.pos)
+    }
+
+    self.walk_expression(body)
+  }
+
+  fn walk_trait(&mut self, statement: &'l Statement<'l>, name: &'l str, body: &'l Expression<'l>) -> Result<(), ()> {
+    self.type_name(name, "trait", &statement.pos);
+    self.walk_expression(body)
+  }
+
+  fn walk_alias(&mut self, statement: &'l Statement<'l>, name: &'l str, _t: &'l Type) -> Result<(), ()> {
+    self.type_name(name, "type", &statement.pos);
+
+    Ok(())
+  }
+
+  fn walk_binary(&mut self, expression: &'l Expression<'l>, left: &'l Expression<'l>, op: &'l Operator, right: &'l Expression<'l>) -> Result<(), ()> {
+    match *op {
+      Operator::Eq | Operator::NEq if self.is_float(left) || self.is_float(right) => self.report(
+        "float_equality",
+        format!("comparing floats with `{}` is unreliable, check whether they're within a small distance of each other instead", op),
+        &expression.pos
+      ),
+
+      _ => (),
+    }
+
+    self.walk_expression(left)?;
+    self.walk_expression(right)
+  }
+
+  fn walk_block(&mut self, expression: &'l Expression<'l>, statements: &'l [Statement<'l>]) -> Result<(), ()> {
+    self.depth += 1;
+
+    if self.depth == MAX_NESTING + 1 {
+      self.report(
+        "deep_nesting",
+        format!("this block is nested {} deep, pull some of it out into a function", self.depth),
+        &expression.pos
+      )
+    }
+
+    let result = self.walk_statements(statements);
+
+    self.depth -= 1;
+
+    result
+  }
+}
@@ -0,0 +1,12 @@
+pub mod config;
+pub mod linter;
+
+#[cfg(test)]
+mod tests;
+
+pub use self::config::*;
+pub use self::linter::*;
+
+use super::source::Source;
+use super::lexer::TokenElement;
+use super::parser::*;
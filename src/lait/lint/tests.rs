@@ -0,0 +1,79 @@
+use super::*;
+
+use super::super::visitor::{ ConstantFolder, Visitor, };
+
+use std::env;
+use std::fs;
+
+// whether the source gets through the linter with `lint` denied
+fn lint(content: &str, lint: &str) -> bool {
+  ::parse("test.lait", content, |source, syntax, ast| {
+    let ast = ConstantFolder::new(source).fold_statements(ast).expect("source doesn't fold");
+
+    let directives  = syntax.directives();
+    let mut visitor = Visitor::new(source, &ast, &directives);
+
+    visitor.visit().expect("source doesn't check");
+
+    let mut config = LintConfig::new();
+
+    config.levels.insert(lint.to_string(), Level::Deny);
+
+    match config.directives(source, &directives) {
+      Ok(()) => Linter::new(source, &config, &visitor.types).lint(&ast),
+      _      => false,
+    }
+  }).expect("source doesn't parse")
+}
+
+#[test]
+fn float_equality_goes_by_type() {
+  assert!(!lint("a := 1.5\nb := a == 2.0\n", "float_equality"));
+  assert!(!lint("a := 1.5\nc := 2.5\nb := a != c\n", "float_equality"));
+  assert!(!lint("fun f(x: f32) -> bool {\n  return x == x\n}\n", "float_equality"));
+  assert!(!lint("a := 1\nb := a as float == 1.0\n", "float_equality"));
+
+  assert!(lint("a := 1\nc := 2\nb := a == c\n", "float_equality"));
+  assert!(lint("a := 1.5\nb := a < 2.0\n", "float_equality"))
+}
+
+#[test]
+fn directives_set_levels() {
+  assert!(lint("-- lait: allow(naming)\nBadName := 1\n", "naming"));
+  assert!(lint("BadName := 1 -- lait: allow(naming)\n", "naming"));
+  assert!(!lint("BadName := 1 -- lait: allow(naming)\nOtherName := 2\n", "naming"));
+
+  // only line comments count
+  assert!(!lint("---\n-- lait: allow(naming)\n---\nBadName := 1\n", "naming"));
+  assert!(!lint("s := \"-- lait: allow(naming)\"\nBadName := 1\n", "naming"))
+}
+
+#[test]
+fn malformed_directives_are_reported() {
+  assert!(!lint("-- lait: allow(naming\nx := 1\n", "naming"));
+  assert!(!lint("-- lait: allow naming\nx := 1\n", "naming"));
+  assert!(!lint("-- lait: sloppy\nx := 1\n", "naming"));
+
+  assert!(lint("-- lait: strict\nx := 1\n", "naming"))
+}
+
+#[test]
+fn config_is_found_above_a_relative_path() {
+  let root = env::temp_dir().join(format!("lait-lint-config-{}", ::std::process::id()));
+  let sub  = root.join("sub");
+
+  fs::create_dir_all(&sub).unwrap();
+  fs::write(root.join(CONFIG_FILE), "naming = deny\n").unwrap();
+  fs::write(sub.join("a.lait"), "x := 1\n").unwrap();
+
+  let current = env::current_dir().unwrap();
+
+  env::set_current_dir(&sub).unwrap();
+
+  let config = LintConfig::load(&Source::from("a.lait", vec!["x := 1".to_string()]), &[]);
+
+  env::set_current_dir(current).unwrap();
+  fs::remove_dir_all(root).unwrap();
+
+  assert_eq!(config.unwrap().level("naming", 1), Level::Deny)
+}
@@ -4,4 +4,6 @@ pub mod error;
 pub mod source;
 pub mod lexer;
 pub mod parser;
-pub mod visitor;
\ No newline at end of file
+pub mod visitor;
+pub mod formatter;
+pub mod lint;
\ No newline at end of file
@@ -8,18 +8,30 @@ use std::fmt;
 pub enum StatementNode<'s> {
   Expression(Expression<'s>),
   Variable(Type, Expression<'s>, Option<Expression<'s>>),
+  Constant(Type, Expression<'s>, Expression<'s>), // `name :: value`, type is nil when inferred
+  Assignment(Expression<'s>, Expression<'s>),
+  For(Expression<'s>, Expression<'s>, Expression<'s>), // binding, iterable, body
+  Function(String, Vec<String>, Vec<(String, Type)>, Type, Expression<'s>), // name, type params, params, return type, body
+  Return(Option<Expression<'s>>),
+  Struct(String, Vec<String>, Expression<'s>), // name, type params, block of field declarations
+  Signature(String, Vec<(String, Type)>, Type), // name, params, return type, a method without a body
+  Trait(String, Expression<'s>), // name, block of method signatures
+  Implement(Option<String>, Type, Expression<'s>), // trait, the type it's for, block of methods
+  Alias(String, Type),
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Statement<'s> {
   pub node: StatementNode<'s>,
   pub pos:  TokenElement<'s>,
+  pub end:  TokenElement<'s>, // the statement's last token, the parser fills it in
 }
 
 impl<'s> Statement<'s> {
   pub fn new(node: StatementNode<'s>, pos: TokenElement<'s>) -> Self {
     Statement {
       node,
+      end: pos.clone(),
       pos,
     }
   }
@@ -29,14 +41,24 @@ impl<'s> Statement<'s> {
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum ExpressionNode<'e> {
-  Int(u128),
+  Int(i128),
   Float(f64),
   String(String),
   Char(char),
   Bool(bool),
+  Nil,
   Identifier(String),
   Block(Vec<Statement<'e>>),
+  If(Rc<Expression<'e>>, Rc<Expression<'e>>, Option<Rc<Expression<'e>>>), // condition, body, else
   Binary(Rc<Expression<'e>>, Operator, Rc<Expression<'e>>),
+  Unary(Operator, Rc<Expression<'e>>),
+  Range(Rc<Expression<'e>>, Rc<Expression<'e>>, bool, Option<Rc<Expression<'e>>>), // inclusive, step
+  Tuple(Vec<Expression<'e>>),
+  List(Vec<Expression<'e>>),
+  Fields(Vec<Expression<'e>>), // `{ x, y }`, only on the left of a declaration
+  Call(Rc<Expression<'e>>, Vec<Expression<'e>>),
+  Cast(Rc<Expression<'e>>, Type),
+  Field(Rc<Expression<'e>>, String),
   EOF,
 }
 
@@ -86,6 +108,20 @@ impl Operator {
     Some(op_prec)
   }
 
+  pub fn precedence(&self) -> u8 {
+    Operator::from_str(self.as_str()).unwrap().1
+  }
+
+  // `^` groups to the right, everything else to the left
+  pub fn is_right_associative(&self) -> bool {
+    *self == Operator::Pow
+  }
+
+  // the operators that give a `bool`
+  pub fn is_comparison(&self) -> bool {
+    self.precedence() == 0
+  }
+
   pub fn as_str(&self) -> &str {
     use self::Operator::*;
     
@@ -0,0 +1,199 @@
+use super::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyntaxKind {
+  Root,
+  Statement,
+  Group, // anything between matching `()`, `[]` or `{}`
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyntaxElement<'s> {
+  Node(SyntaxNode<'s>),
+  Token(Token<'s>),
+}
+
+// a `-- lait: strict` line comment, trailing when code precedes it on its line
+#[derive(Debug, Clone, PartialEq)]
+pub struct Directive {
+  pub line:     usize,
+  pub text:     String,
+  pub trailing: bool,
+}
+
+// the concrete syntax tree keeps every token of the lossless stream,
+// so whitespace, comments and blank lines survive a round-trip
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxNode<'s> {
+  pub kind:     SyntaxKind,
+  pub children: Vec<SyntaxElement<'s>>,
+}
+
+impl<'s> SyntaxNode<'s> {
+  pub fn new(kind: SyntaxKind, children: Vec<SyntaxElement<'s>>) -> Self {
+    SyntaxNode {
+      kind,
+      children,
+    }
+  }
+
+  pub fn build(tokens: &[Token<'s>]) -> Self {
+    let mut index = 0;
+    let mut children = Self::build_sequence(tokens, &mut index, None);
+
+    // stray closing brackets are kept as plain tokens
+    while index < tokens.len() {
+      children.push(SyntaxElement::Token(tokens[index].clone()));
+      index += 1;
+
+      children.extend(Self::build_sequence(tokens, &mut index, None))
+    }
+
+    SyntaxNode::new(SyntaxKind::Root, children)
+  }
+
+  fn build_sequence(tokens: &[Token<'s>], index: &mut usize, closer: Option<&str>) -> Vec<SyntaxElement<'s>> {
+    let mut children = Vec::new();
+
+    while let Some(token) = tokens.get(*index) {
+      if Self::is_closer(token, closer) {
+        break
+      }
+
+      if token.token_type.is_trivia() || token.token_type == TokenType::EOL {
+        children.push(SyntaxElement::Token(token.clone()));
+        *index += 1;
+
+        continue
+      }
+
+      children.push(SyntaxElement::Node(Self::build_statement(tokens, index, closer)))
+    }
+
+    children
+  }
+
+  fn build_statement(tokens: &[Token<'s>], index: &mut usize, closer: Option<&str>) -> Self {
+    let mut children = Vec::new();
+
+    while let Some(token) = tokens.get(*index) {
+      if token.token_type == TokenType::EOL || Self::is_closer(token, closer) {
+        break
+      }
+
+      if Self::is_opener(token) {
+        children.push(SyntaxElement::Node(Self::build_group(tokens, index)))
+      } else {
+        children.push(SyntaxElement::Token(token.clone()));
+        *index += 1
+      }
+    }
+
+    SyntaxNode::new(SyntaxKind::Statement, children)
+  }
+
+  fn build_group(tokens: &[Token<'s>], index: &mut usize) -> Self {
+    let open   = tokens[*index].clone();
+    let closer = match open.lexeme.as_str() {
+      "(" => ")",
+      "[" => "]",
+      _   => "}",
+    };
+
+    *index += 1;
+
+    let mut children = vec!(SyntaxElement::Token(open.clone()));
+
+    if open.lexeme == "{" {
+      children.extend(Self::build_sequence(tokens, index, Some(closer)))
+    } else {
+      while let Some(token) = tokens.get(*index) {
+        if Self::is_closer(token, Some(closer)) {
+          break
+        }
+
+        if Self::is_opener(token) {
+          children.push(SyntaxElement::Node(Self::build_group(tokens, index)))
+        } else {
+          children.push(SyntaxElement::Token(token.clone()));
+          *index += 1
+        }
+      }
+    }
+
+    if let Some(token) = tokens.get(*index) {
+      if Self::is_closer(token, Some(closer)) {
+        children.push(SyntaxElement::Token(token.clone()));
+        *index += 1
+      }
+    }
+
+    SyntaxNode::new(SyntaxKind::Group, children)
+  }
+
+  fn is_opener(token: &Token<'s>) -> bool {
+    token.token_type == TokenType::Symbol && ["(", "[", "{"].contains(&token.lexeme.as_str())
+  }
+
+  fn is_closer(token: &Token<'s>, closer: Option<&str>) -> bool {
+    match closer {
+      Some(c) => token.token_type == TokenType::Symbol && token.lexeme == c,
+      None    => false,
+    }
+  }
+
+  pub fn tokens(&self) -> Vec<&Token<'s>> {
+    let mut tokens = Vec::new();
+
+    for child in &self.children {
+      match *child {
+        SyntaxElement::Node(ref node)   => tokens.extend(node.tokens()),
+        SyntaxElement::Token(ref token) => tokens.push(token),
+      }
+    }
+
+    tokens
+  }
+
+  // the tokens the parser cares about, in order
+  pub fn significant(&self) -> Vec<&Token<'s>> {
+    self.tokens().into_iter().filter(|t| !t.token_type.is_trivia()).collect()
+  }
+
+  // read off the comment tokens, so block comments and strings never count
+  pub fn directives(&self) -> Vec<Directive> {
+    let mut directives = Vec::new();
+    let mut has_code   = false;
+
+    for token in self.tokens() {
+      match token.token_type {
+        TokenType::EOL => has_code = false,
+
+        TokenType::Comment => if !token.raw.starts_with("---") {
+          let comment = token.raw[2 ..].trim_start();
+
+          if comment.starts_with("lait:") {
+            directives.push(
+              Directive {
+                line:     token.line.0,
+                text:     comment["lait:".len() ..].trim().to_string(),
+                trailing: has_code,
+              }
+            )
+          }
+        },
+
+        TokenType::Whitespace => (),
+
+        _ => has_code = true,
+      }
+    }
+
+    directives
+  }
+
+  // rebuilds the exact source text
+  pub fn text(&self) -> String {
+    self.tokens().iter().map(|t| t.raw.as_str()).collect()
+  }
+}
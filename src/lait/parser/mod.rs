@@ -1,8 +1,17 @@
 pub mod ast;
+pub mod cst;
 pub mod parser;
+pub mod serialize;
+pub mod walk;
+
+#[cfg(test)]
+mod tests;
 
 pub use self::ast::*;
+pub use self::cst::*;
 pub use self::parser::*;
+pub use self::serialize::*;
+pub use self::walk::*;
 
 use super::source::*;
 use super::lexer::{ TokenElement, Token, TokenType, };
@@ -25,7 +25,14 @@ impl<'p> Parser<'p> {
     let mut ast = Vec::new();
 
     while self.remaining() > 0 {
-      ast.push(self.parse_statement()?)
+      let statement = self.parse_statement()?;
+
+      // trailing lines leave nothing but an EOF behind
+      if let StatementNode::Expression(Expression { node: ExpressionNode::EOF, .. }) = statement.node {
+        break
+      }
+
+      ast.push(statement)
     }
 
     Ok(ast)
@@ -38,7 +45,42 @@ impl<'p> Parser<'p> {
       self.next()?
     }
 
-    let statement = match *self.current_type() {
+    let mut statement = match *self.current_type() {
+      Keyword => match self.current_lexeme().as_str() {
+        "for"    => self.parse_for()?,
+        "fun"    => self.parse_function()?,
+        "return" => self.parse_return()?,
+        "struct" => self.parse_struct()?,
+        "trait"  => self.parse_trait()?,
+
+        "implement" => self.parse_implement()?,
+        "type"   => self.parse_alias()?,
+
+        "if" => {
+          let expression = self.parse_expression()?;
+          let position   = expression.pos.clone();
+
+          Statement::new(
+            StatementNode::Expression(expression),
+            position,
+          )
+        },
+
+        ref c => return Err(
+          response!(
+            Wrong(format!("unexpected keyword `{}`", c)),
+            self.source.file,
+            TokenElement::Ref(self.current())
+          )
+        )
+      },
+
+      Symbol if self.is_pattern() => {
+        let pattern = self.parse_pattern()?;
+
+        self.parse_declaration(pattern)?
+      },
+
       _ => {
         use self::ExpressionNode::*;
 
@@ -50,6 +92,24 @@ impl<'p> Parser<'p> {
               if self.current_type() == &TokenType::Symbol {
                 let statement = match self.current_lexeme().as_str() {
                   ":"   => self.parse_declaration(expression)?,
+                  ","   => {
+                    let names = self.parse_names(expression)?;
+
+                    self.parse_declaration(names)?
+                  },
+
+                  "="   => self.parse_assignment(expression)?,
+
+                  // the last value of a block, `{ x }`
+                  "}"   => {
+                    let position = expression.pos.clone();
+
+                    Statement::new(
+                      StatementNode::Expression(expression),
+                      position
+                    )
+                  },
+
                   ref c => return Err(
                     response!(
                       Wrong(format!("unexpected symbol `{}`", c)),
@@ -78,6 +138,8 @@ impl<'p> Parser<'p> {
             }
           },
 
+          Field(..) if self.remaining() > 0 && self.current_lexeme() == "=" => self.parse_assignment(expression)?,
+
           _ => {
             let position = expression.pos.clone();
 
@@ -90,10 +152,22 @@ impl<'p> Parser<'p> {
       },
     };
 
+    statement.end = self.last_position();
+
     Ok(statement)
   }
 
   pub fn parse_expression(&mut self) -> Result<Expression<'p>, ()> {
+    let expression = self.parse_operation()?;
+
+    if self.current_type() == &TokenType::Symbol && ["..", "..="].contains(&self.current_lexeme().as_str()) {
+      self.parse_range(expression)
+    } else {
+      Ok(expression)
+    }
+  }
+
+  fn parse_operation(&mut self) -> Result<Expression<'p>, ()> {
     let atom = self.parse_atom()?;
 
     if self.current_type() == &TokenType::Operator {
@@ -103,6 +177,31 @@ impl<'p> Parser<'p> {
     }
   }
 
+  fn parse_range(&mut self, start: Expression<'p>) -> Result<Expression<'p>, ()> {
+    let inclusive = self.eat()? == "..=";
+    let end       = self.parse_operation()?;
+
+    let step = if self.current_type() == &TokenType::Keyword && self.current_lexeme() == "by" {
+      self.next()?;
+
+      Some(Rc::new(self.parse_operation()?))
+    } else {
+      None
+    };
+
+    let position = match step {
+      Some(ref step) => Self::span_between(&start.pos, &step.pos),
+      None           => Self::span_between(&start.pos, &end.pos),
+    };
+
+    Ok(
+      Expression::new(
+        ExpressionNode::Range(Rc::new(start), Rc::new(end), inclusive, step),
+        position
+      )
+    )
+  }
+
   fn parse_atom(&mut self) -> Result<Expression<'p>, ()> {
     use self::TokenType::*;
 
@@ -119,7 +218,7 @@ impl<'p> Parser<'p> {
 
       let expression = match token_type {
         Int => Expression::new(
-          ExpressionNode::Int(self.eat()?.parse::<u128>().unwrap()),
+          ExpressionNode::Int(self.eat()?.parse::<i128>().unwrap()),
           position
         ),
 
@@ -148,6 +247,38 @@ impl<'p> Parser<'p> {
           position
         ),
 
+        Symbol if self.current_lexeme() == "{" => self.parse_block()?,
+        Symbol if self.current_lexeme() == "(" => self.parse_parens()?,
+
+        // prefix minus binds to the operand alone, so `-a ^ 2` is `(-a) ^ 2`
+        Operator if self.current_lexeme() == "-" => {
+          self.next()?;
+
+          let operand  = self.parse_atom()?;
+          let position = Self::span_between(&position, &operand.pos);
+
+          return Ok(Expression::new(ExpressionNode::Unary(super::ast::Operator::Sub, Rc::new(operand)), position))
+        },
+
+        Symbol if self.current_lexeme() == "[" => {
+          self.next()?;
+
+          let elements = self.parse_list("]")?;
+          let position = self.span_from(position);
+
+          self.eat_lexeme("]")?;
+
+          Expression::new(ExpressionNode::List(elements), position)
+        },
+
+        Keyword if self.current_lexeme() == "if" => self.parse_if()?,
+
+        Keyword if self.current_lexeme() == "nil" => {
+          self.next()?;
+
+          Expression::new(ExpressionNode::Nil, position)
+        },
+
         _ => return Err(
           response!(
             Wrong("unimplemented af"),
@@ -157,69 +288,571 @@ impl<'p> Parser<'p> {
         )
       };
 
-      Ok(expression)
+      self.parse_postfix(expression)
+    }
+  }
+
+  // `(a)` only groups, a comma makes it a tuple
+  fn parse_parens(&mut self) -> Result<Expression<'p>, ()> {
+    let position = self.current_position();
+
+    self.next()?;
+
+    let mut elements = self.parse_list(")")?;
+
+    let expression = if elements.len() == 1 {
+      elements.remove(0)
+    } else if elements.is_empty() {
+      return Err(
+        response!(
+          Wrong("expected expression, found `()`"),
+          self.source.file,
+          self.span_from(position)
+        )
+      )
+    } else {
+      Expression::new(ExpressionNode::Tuple(elements), self.span_from(position))
+    };
+
+    self.eat_lexeme(")")?;
+
+    Ok(expression)
+  }
+
+  // calls and `as` casts, binding tighter than any operator
+  fn parse_postfix(&mut self, mut expression: Expression<'p>) -> Result<Expression<'p>, ()> {
+    while self.remaining() > 0 {
+      expression = match (self.current_type().clone(), self.current_lexeme().as_str()) {
+        (TokenType::Symbol, "(") => {
+          self.next()?;
+
+          let arguments = self.parse_list(")")?;
+          let position  = self.span_from(expression.pos.clone());
+
+          self.eat_lexeme(")")?;
+
+          Expression::new(ExpressionNode::Call(Rc::new(expression), arguments), position)
+        },
+
+        (TokenType::Symbol, ".") => {
+          self.next()?;
+
+          let field    = self.eat_type(&TokenType::Identifier)?;
+          let position = Self::span_between(&expression.pos, &self.previous_position());
+
+          Expression::new(ExpressionNode::Field(Rc::new(expression), field), position)
+        },
+
+        (TokenType::Keyword, "as") => {
+          self.next()?;
+
+          let type_position = self.current_position();
+          let t             = self.parse_cast_type()?;
+          let position      = Self::span_between(&expression.pos, &type_position);
+
+          Expression::new(ExpressionNode::Cast(Rc::new(expression), t), position)
+        },
+
+        _ => break,
+      }
+    }
+
+    Ok(expression)
+  }
+
+  // comma separated expressions up to, but not including, `close`
+  fn parse_list(&mut self, close: &str) -> Result<Vec<Expression<'p>>, ()> {
+    let mut elements = Vec::new();
+
+    while self.current_lexeme() != close {
+      elements.push(self.parse_expression()?);
+
+      if self.current_lexeme() == "," {
+        self.next()?
+      } else {
+        break
+      }
     }
+
+    Ok(elements)
   }
 
+  // precedence climbing, operators of equal precedence group to the left except `^`
   fn parse_binary(&mut self, left: Expression<'p>) -> Result<Expression<'p>, ()> {
-    let left_position = left.pos.clone();
+    self.parse_binary_from(left, 0)
+  }
 
-    let mut expression_stack = vec!(left);
-    let mut operator_stack   = vec!(Operator::from_str(&self.eat()?).unwrap());
+  fn parse_binary_from(&mut self, mut left: Expression<'p>, min_precedence: u8) -> Result<Expression<'p>, ()> {
+    while self.current_type() == &TokenType::Operator {
+      let (operator, precedence) = Operator::from_str(&self.current_lexeme()).unwrap();
 
-    expression_stack.push(self.parse_atom()?);
+      if precedence < min_precedence {
+        break
+      }
 
-    while operator_stack.len() > 0 {
-      while self.current_type() == &TokenType::Operator {
-        let position               = self.current_position();
-        let (operator, precedence) = Operator::from_str(&self.eat()?).unwrap();
+      let position = self.current_position();
 
-        if precedence < operator_stack.last().unwrap().1 {
-          let right = expression_stack.pop().unwrap();
-          let left  = expression_stack.pop().unwrap();
+      self.next()?;
 
-          expression_stack.push(
-            Expression::new(
-              ExpressionNode::Binary(Rc::new(left), operator_stack.pop().unwrap().0, Rc::new(right)),
-              self.current_position(),
-            )
-          );
-
-          if self.remaining() > 0 {
-            expression_stack.push(self.parse_atom()?);
-            operator_stack.push((operator, precedence))
-          } else {
-            return Err(
-              response!(
-                Wrong("reached EOF in operation"),
-                self.source.file,
-                position
-              )
-            )
-          }
+      if self.remaining() == 0 {
+        return Err(
+          response!(
+            Wrong("reached EOF in operation"),
+            self.source.file,
+            position
+          )
+        )
+      }
+
+      let mut right = self.parse_atom()?;
+
+      while self.current_type() == &TokenType::Operator {
+        let (next, next_precedence) = Operator::from_str(&self.current_lexeme()).unwrap();
+
+        if next_precedence > precedence {
+          right = self.parse_binary_from(right, precedence + 1)?
+        } else if next_precedence == precedence && next.is_right_associative() {
+          right = self.parse_binary_from(right, precedence)?
         } else {
-          expression_stack.push(self.parse_atom()?);
-          operator_stack.push((operator, precedence))
+          break
         }
       }
 
-      let right = expression_stack.pop().unwrap();
-      let left  = expression_stack.pop().unwrap();
+      let span = Self::span_between(&left.pos, &right.pos);
 
-      expression_stack.push(
-        Expression::new(
-          ExpressionNode::Binary(Rc::new(left), operator_stack.pop().unwrap().0, Rc::new(right)),
-          self.current_position(),
+      left = Expression::new(
+        ExpressionNode::Binary(Rc::new(left), operator, Rc::new(right)),
+        span,
+      )
+    }
+
+    Ok(left)
+  }
+
+  fn parse_for(&mut self) -> Result<Statement<'p>, ()> {
+    let position = self.current_position();
+
+    self.next()?;
+
+    let binding = if self.current_type() == &TokenType::Identifier {
+      let position = self.current_position();
+
+      Expression::new(ExpressionNode::Identifier(self.eat()?), position)
+    } else {
+      return Err(
+        response!(
+          Wrong(format!("expected loop variable, found `{}`", self.current_lexeme())),
+          self.source.file,
+          self.current_position()
+        )
+      )
+    };
+
+    self.eat_lexeme("in")?;
+
+    let iterable = self.parse_expression()?;
+    let body     = self.parse_block()?;
+
+    Ok(
+      Statement::new(
+        StatementNode::For(binding, iterable, body),
+        position
+      )
+    )
+  }
+
+  fn parse_function(&mut self) -> Result<Statement<'p>, ()> {
+    let position = self.current_position();
+
+    self.next()?;
+
+    // operators are named by their symbol, `fun +(self, other: Vec2) -> Vec2`
+    let name = if self.current_type() == &TokenType::Operator {
+      self.eat()?
+    } else {
+      self.eat_type(&TokenType::Identifier)?
+    };
+
+    let generics = self.parse_generics()?;
+
+    self.eat_lexeme("(")?;
+
+    let mut params = Vec::new();
+
+    while self.current_lexeme() != ")" {
+      let param = self.eat_type(&TokenType::Identifier)?;
+
+      // `self` goes without a type, it's whatever the method is implemented for, other untyped params are `any`
+      if self.current_lexeme() != ":" {
+        params.push((param, Type::Nil));
+      } else {
+        self.eat_lexeme(":")?;
+
+        params.push((param, self.parse_type()?));
+      }
+
+      if self.current_lexeme() == "," {
+        self.next()?
+      } else {
+        break
+      }
+    }
+
+    self.eat_lexeme(")")?;
+
+    let return_type = if self.current_lexeme() == "->" {
+      self.next()?;
+      self.parse_type()?
+    } else {
+      Type::Nil
+    };
+
+    // without a body it's a signature, as found in traits
+    if self.remaining() == 0 || self.current_lexeme() != "{" {
+      if !generics.is_empty() {
+        return Err(
+          response!(
+            Wrong("methods without a body can't have type parameters"),
+            self.source.file,
+            position
+          )
+        )
+      }
+
+      return Ok(
+        Statement::new(
+          StatementNode::Signature(name, params, return_type),
+          position
+        )
+      )
+    }
+
+    let body = self.parse_block()?;
+
+    Ok(
+      Statement::new(
+        StatementNode::Function(name, generics, params, return_type, body),
+        position
+      )
+    )
+  }
+
+  // `return a, b` hands back a tuple
+  fn parse_return(&mut self) -> Result<Statement<'p>, ()> {
+    let position = self.current_position();
+
+    self.next()?;
+
+    if self.remaining() == 0 || self.current_type() == &TokenType::EOL || self.current_lexeme() == "}" {
+      return Ok(Statement::new(StatementNode::Return(None), position))
+    }
+
+    let mut values = vec!(self.parse_expression()?);
+
+    while self.current_lexeme() == "," {
+      self.next()?;
+
+      values.push(self.parse_expression()?)
+    }
+
+    let value = if values.len() == 1 {
+      values.remove(0)
+    } else {
+      let span = Self::span_between(&values[0].pos, &values[values.len() - 1].pos);
+
+      Expression::new(ExpressionNode::Tuple(values), span)
+    };
+
+    Ok(Statement::new(StatementNode::Return(Some(value)), position))
+  }
+
+  fn parse_struct(&mut self) -> Result<Statement<'p>, ()> {
+    let position = self.current_position();
+
+    self.next()?;
+
+    let name     = self.eat_type(&TokenType::Identifier)?;
+    let generics = self.parse_generics()?;
+    let body     = self.parse_block()?;
+
+    Ok(
+      Statement::new(
+        StatementNode::Struct(name, generics, body),
+        position
+      )
+    )
+  }
+
+  fn parse_trait(&mut self) -> Result<Statement<'p>, ()> {
+    let position = self.current_position();
+
+    self.next()?;
+
+    let name = self.eat_type(&TokenType::Identifier)?;
+    let body = self.parse_block()?;
+
+    Ok(
+      Statement::new(
+        StatementNode::Trait(name, body),
+        position
+      )
+    )
+  }
+
+  // `implement Drawable for Sprite { .. }`, or `implement Sprite { .. }` for methods of its own
+  fn parse_implement(&mut self) -> Result<Statement<'p>, ()> {
+    let position = self.current_position();
+
+    self.next()?;
+
+    let name_position = self.current_position();
+
+    let mut t          = self.parse_type()?;
+    let mut trait_name = None;
+
+    if self.current_lexeme() == "for" {
+      self.next()?;
+
+      trait_name = match t {
+        Type::Id(name) => Some(name),
+
+        _ => return Err(
+          response!(
+            Wrong("expected the name of a trait"),
+            self.source.file,
+            name_position
+          )
         )
-      );
+      };
+
+      t = self.parse_type()?
+    }
+
+    let body = self.parse_block()?;
+
+    Ok(
+      Statement::new(
+        StatementNode::Implement(trait_name, t, body),
+        position
+      )
+    )
+  }
+
+  // the type parameters of `fun first<T>` and `struct Pool<T>`
+  fn parse_generics(&mut self) -> Result<Vec<String>, ()> {
+    let mut generics = Vec::new();
+
+    if self.current_lexeme() != "<" {
+      return Ok(generics)
+    }
+
+    self.next()?;
+
+    while self.current_lexeme() != ">" {
+      generics.push(self.eat_type(&TokenType::Identifier)?);
+
+      if self.current_lexeme() == "," {
+        self.next()?
+      } else {
+        break
+      }
+    }
+
+    self.eat_lexeme(">")?;
+
+    Ok(generics)
+  }
+
+  fn parse_alias(&mut self) -> Result<Statement<'p>, ()> {
+    let position = self.current_position();
+
+    self.next()?;
+
+    let name = self.eat_type(&TokenType::Identifier)?;
+
+    self.eat_lexeme("=")?;
+
+    let t = self.parse_type()?;
+
+    Ok(
+      Statement::new(
+        StatementNode::Alias(name, t),
+        position
+      )
+    )
+  }
+
+  // a bracket at the start of a statement is a pattern when a `:` follows its closing bracket
+  fn is_pattern(&self) -> bool {
+    if !["{", "[", "("].contains(&self.current_lexeme().as_str()) {
+      return false
+    }
+
+    let mut depth = 0;
+
+    for (i, token) in self.tokens[self.index ..].iter().enumerate() {
+      match (&token.token_type, token.lexeme.as_str()) {
+        (&TokenType::Symbol, "{") | (&TokenType::Symbol, "[") | (&TokenType::Symbol, "(") => depth += 1,
+
+        (&TokenType::Symbol, "}") | (&TokenType::Symbol, "]") | (&TokenType::Symbol, ")") => {
+          depth -= 1;
+
+          if depth == 0 {
+            return self.tokens.get(self.index + i + 1).map_or(false, |t| t.lexeme == ":")
+          }
+        },
+
+        (&TokenType::EOL, _) => return false,
+
+        _ => (),
+      }
+    }
+
+    false
+  }
+
+  // `name`, `(a, b)`, `[first, second]` or `{ x, y }`
+  fn parse_pattern(&mut self) -> Result<Expression<'p>, ()> {
+    let position = self.current_position();
+
+    let (close, fields) = match self.current_lexeme().as_str() {
+      "(" => (")", false),
+      "[" => ("]", false),
+      "{" => ("}", true),
+
+      _ => return Ok(
+        Expression::new(ExpressionNode::Identifier(self.eat_type(&TokenType::Identifier)?), position)
+      )
+    };
+
+    self.next()?;
+
+    let mut elements = Vec::new();
+
+    while self.current_lexeme() != close {
+      if fields {
+        let position = self.current_position();
+
+        elements.push(Expression::new(ExpressionNode::Identifier(self.eat_type(&TokenType::Identifier)?), position))
+      } else {
+        elements.push(self.parse_pattern()?)
+      }
+
+      if self.current_lexeme() == "," {
+        self.next()?
+      } else {
+        break
+      }
+    }
+
+    let position = self.span_from(position);
+
+    self.eat_lexeme(close)?;
+
+    let node = match close {
+      ")" if elements.len() == 1 => return Ok(elements.remove(0)),
+      ")"                        => ExpressionNode::Tuple(elements),
+      "]" => ExpressionNode::List(elements),
+      _   => ExpressionNode::Fields(elements),
+    };
+
+    Ok(Expression::new(node, position))
+  }
+
+  // the left side of `a, b := ...`
+  fn parse_names(&mut self, first: Expression<'p>) -> Result<Expression<'p>, ()> {
+    let mut names = vec!(first);
+
+    while self.current_lexeme() == "," {
+      self.next()?;
+
+      let position = self.current_position();
+
+      names.push(Expression::new(ExpressionNode::Identifier(self.eat_type(&TokenType::Identifier)?), position))
+    }
+
+    let span = Self::span_between(&names[0].pos, &names[names.len() - 1].pos);
+
+    Ok(Expression::new(ExpressionNode::Tuple(names), span))
+  }
+
+  fn parse_if(&mut self) -> Result<Expression<'p>, ()> {
+    let position = self.current_position();
+
+    self.next()?;
+
+    let condition = self.parse_expression()?;
+    let body      = self.parse_block()?;
+
+    let otherwise = if self.remaining() > 0 && self.current_type() == &TokenType::Keyword && self.current_lexeme() == "else" {
+      self.next()?;
+
+      if self.current_type() == &TokenType::Keyword && self.current_lexeme() == "if" {
+        Some(Rc::new(self.parse_if()?))
+      } else {
+        Some(Rc::new(self.parse_block()?))
+      }
+    } else {
+      None
+    };
+
+    Ok(
+      Expression::new(
+        ExpressionNode::If(Rc::new(condition), Rc::new(body), otherwise),
+        position
+      )
+    )
+  }
+
+  fn parse_block(&mut self) -> Result<Expression<'p>, ()> {
+    let position = self.current_position();
+
+    self.eat_lexeme("{")?;
+
+    let mut statements = Vec::new();
+
+    loop {
+      while self.remaining() > 0 && self.current_type() == &TokenType::EOL {
+        self.next()?
+      }
+
+      if self.remaining() == 0 {
+        return Err(
+          response!(
+            Wrong("unterminated block, expected `}`"),
+            self.source.file,
+            position
+          )
+        )
+      }
+
+      if self.current_type() == &TokenType::Symbol && self.current_lexeme() == "}" {
+        break
+      }
+
+      statements.push(self.parse_statement()?)
     }
 
-    let expression = expression_stack.pop().unwrap();
+    self.next()?;
 
     Ok(
       Expression::new(
-        expression.node,
-        self.span_from(left_position)
+        ExpressionNode::Block(statements),
+        position
+      )
+    )
+  }
+
+  fn parse_assignment(&mut self, left: Expression<'p>) -> Result<Statement<'p>, ()> {
+    self.next()?;
+
+    let right    = self.parse_expression()?;
+    let position = left.pos.clone();
+
+    Ok(
+      Statement::new(
+        StatementNode::Assignment(left, right),
+        position
       )
     )
   }
@@ -232,6 +865,23 @@ impl<'p> Parser<'p> {
         let position = left.pos.clone();
 
         match self.current_lexeme().as_str() {
+          ":" => {
+            self.next()?;
+
+            let right = self.parse_expression()?;
+
+            Ok(
+              Statement::new(
+                StatementNode::Constant(
+                  Type::Nil,
+                  left,
+                  right,
+                ),
+
+                position,
+              )
+            )
+          },
 
           "=" => {
             self.next()?;
@@ -256,6 +906,24 @@ impl<'p> Parser<'p> {
             let t = self.parse_type()?;
 
             match self.current_lexeme().as_str() {
+              ":" => {
+                self.next()?;
+
+                let right = self.parse_expression()?;
+
+                Ok(
+                  Statement::new(
+                    StatementNode::Constant(
+                      t,
+                      left,
+                      right,
+                    ),
+
+                    position,
+                  )
+                )
+              },
+
               "=" => {
                 self.next()?;
 
@@ -307,13 +975,57 @@ impl<'p> Parser<'p> {
     use self::TokenType::*;
 
     let t = match *self.current_type() {
-      Identifier => match self.eat()?.as_str() {
-        "str"   => Type::String,
-        "int"   => Type::Int,
-        "float" => Type::Float,
-        "bool"  => Type::Bool,
-        "char"  => Type::Char,
-        id      => Type::Id(id.to_owned()),
+      // `?` can end identifiers, so `int?` comes in as one token
+      Identifier => {
+        let id = self.eat()?;
+
+        if id.ends_with('?') {
+          Type::Optional(Rc::new(Self::named_type(id.trim_end_matches('?'))))
+        } else if self.remaining() > 0 && self.current_lexeme() == "<" {
+          self.next()?;
+
+          Type::Instance(id, self.parse_types(">")?)
+        } else {
+          Self::named_type(&id)
+        }
+      },
+
+      Symbol if self.current_lexeme() == "(" => {
+        self.next()?;
+
+        let mut types = self.parse_types(")")?;
+
+        if types.len() == 1 {
+          types.remove(0)
+        } else {
+          Type::Tuple(types)
+        }
+      },
+
+      Symbol if self.current_lexeme() == "[" => {
+        self.next()?;
+
+        let t = self.parse_type()?;
+
+        self.eat_lexeme("]")?;
+
+        Type::List(Rc::new(t))
+      },
+
+      Keyword if self.current_lexeme() == "fun" => {
+        self.next()?;
+        self.eat_lexeme("(")?;
+
+        let params = self.parse_types(")")?;
+
+        let return_type = if self.current_lexeme() == "->" {
+          self.next()?;
+          self.parse_type()?
+        } else {
+          Type::Nil
+        };
+
+        Type::Fun(params, Rc::new(return_type))
       },
 
       _ => return Err(
@@ -325,9 +1037,72 @@ impl<'p> Parser<'p> {
       )
     };
 
+    // `[int]?` and `(int, str)?`
+    if self.remaining() > 0 && self.current_lexeme() == "?" {
+      self.next()?;
+
+      return Ok(Type::Optional(Rc::new(t)))
+    }
+
     Ok(t)
   }
 
+  // `hp as int < 5` compares, so the name a cast ends on takes no type arguments
+  fn parse_cast_type(&mut self) -> Result<Type, ()> {
+    if *self.current_type() != TokenType::Identifier {
+      return self.parse_type()
+    }
+
+    let id = self.eat()?;
+
+    if id.ends_with('?') {
+      Ok(Type::Optional(Rc::new(Self::named_type(id.trim_end_matches('?')))))
+    } else {
+      Ok(Self::named_type(&id))
+    }
+  }
+
+  fn named_type(name: &str) -> Type {
+    match name {
+      "str"   => Type::String,
+      "int"   => Type::Int,
+      "float" => Type::Float,
+      "bool"  => Type::Bool,
+      "char"  => Type::Char,
+      "i8"    => Type::I8,
+      "i16"   => Type::I16,
+      "i32"   => Type::I32,
+      "i64"   => Type::I64,
+      "u8"    => Type::U8,
+      "u16"   => Type::U16,
+      "u32"   => Type::U32,
+      "u64"   => Type::U64,
+      "f32"   => Type::F32,
+      "f64"   => Type::F64,
+      "any"   => Type::Any,
+      id      => Type::Id(id.to_owned()),
+    }
+  }
+
+  // comma separated types, eating `close`
+  fn parse_types(&mut self, close: &str) -> Result<Vec<Type>, ()> {
+    let mut types = Vec::new();
+
+    while self.current_lexeme() != close {
+      types.push(self.parse_type()?);
+
+      if self.current_lexeme() == "," {
+        self.next()?
+      } else {
+        break
+      }
+    }
+
+    self.eat_lexeme(close)?;
+
+    Ok(types)
+  }
+
 
 
   fn next(&mut self) -> Result<(), ()> {
@@ -357,6 +1132,25 @@ impl<'p> Parser<'p> {
     )
   }
 
+  fn previous_position(&self) -> TokenElement<'p> {
+    let previous = self.tokens[self.index.saturating_sub(1)];
+
+    TokenElement::Pos(
+      previous.line,
+      previous.slice
+    )
+  }
+
+  // the last token consumed that isn't a line break
+  fn last_position(&self) -> TokenElement<'p> {
+    let last = self.tokens[.. self.index.min(self.tokens.len())].iter().rev().find(|t| t.token_type != TokenType::EOL && t.token_type != TokenType::EOF);
+
+    match last {
+      Some(token) => TokenElement::Pos(token.line, token.slice),
+      None        => self.previous_position(),
+    }
+  }
+
   fn span_from(&self, left_position: TokenElement<'p>) -> TokenElement<'p> {
     match left_position {
       TokenElement::Pos(ref line, ref slice) => if let TokenElement::Pos(_, ref slice2) = self.current_position() {
@@ -369,6 +1163,17 @@ impl<'p> Parser<'p> {
     }
   }
 
+  // covers both ends when they share a line
+  fn span_between(left: &TokenElement<'p>, right: &TokenElement<'p>) -> TokenElement<'p> {
+    match (left, right) {
+      (&TokenElement::Pos(ref line, ref slice), &TokenElement::Pos(ref line2, ref slice2)) if line.0 == line2.0 => {
+        TokenElement::Pos(*line, (slice.0, slice2.1))
+      },
+
+      _ => left.clone(),
+    }
+  }
+
   fn current(&self) -> &'p Token<'p> {
     if self.index > self.tokens.len() - 1 {
       &self.tokens[self.tokens.len() - 1]
@@ -438,37 +1243,4 @@ impl<'p> Parser<'p> {
       )
     }
   }
-
-
-
-  pub fn fold_expression<'v>(expression: &Expression<'v>) -> Result<Expression<'v>, ()> {
-    use self::ExpressionNode::*;
-    use self::Operator::*;
-
-    let node = match expression.node {
-      Binary(ref left, ref op, ref right) => {
-        let node = match (&Self::fold_expression(&*left)?.node, op, &Self::fold_expression(&*right)?.node) {
-          (&Int(ref a),   &Add, &Int(ref b))   => Int(a + b),
-          (&Float(ref a), &Add, &Float(ref b)) => Float(a + b),
-          (&Int(ref a),   &Sub, &Int(ref b))   => Int(a - b),
-          (&Float(ref a), &Sub, &Float(ref b)) => Float(a - b),
-          (&Int(ref a),   &Mul, &Int(ref b))   => Int(a * b),
-          (&Float(ref a), &Mul, &Float(ref b)) => Float(a * b),
-          (&Int(ref a),   &Div, &Int(ref b))   => Int(a / b),
-          (&Float(ref a), &Div, &Float(ref b)) => Float(a / b),
-
-          _ => expression.node.clone()
-        };
-
-        Expression::new(
-          node,
-          expression.pos.clone()
-        )
-      },
-
-      _ => expression.clone()
-    };
-
-    Ok(node)
-  }
-}
\ No newline at end of file
+}
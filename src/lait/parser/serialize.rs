@@ -0,0 +1,438 @@
+use super::*;
+
+// stable, dependency-free dumps of the tree for tools outside of Rust
+pub trait Serialize {
+  fn to_json(&self) -> String;
+  fn to_sexp(&self) -> String;
+}
+
+
+
+impl<'s> Serialize for [Statement<'s>] {
+  fn to_json(&self) -> String {
+    format!("[{}]", self.iter().map(|s| s.to_json()).collect::<Vec<String>>().join(","))
+  }
+
+  fn to_sexp(&self) -> String {
+    self.iter().map(|s| s.to_sexp()).collect::<Vec<String>>().join("\n")
+  }
+}
+
+impl<'s> Serialize for Statement<'s> {
+  fn to_json(&self) -> String {
+    use self::StatementNode::*;
+
+    let fields = match self.node {
+      Expression(ref expression) => format!(
+        "\"kind\":\"expression\",\"expression\":{}", expression.to_json()
+      ),
+
+      Variable(ref t, ref left, ref right) => format!(
+        "\"kind\":\"variable\",\"type\":{},\"left\":{},\"right\":{}",
+        json_type(t),
+        left.to_json(),
+        right.as_ref().map_or("null".to_string(), |r| r.to_json())
+      ),
+
+      Constant(ref t, ref left, ref right) => format!(
+        "\"kind\":\"constant\",\"type\":{},\"left\":{},\"right\":{}",
+        json_type(t),
+        left.to_json(),
+        right.to_json()
+      ),
+
+      Assignment(ref left, ref right) => format!(
+        "\"kind\":\"assignment\",\"left\":{},\"right\":{}",
+        left.to_json(),
+        right.to_json()
+      ),
+
+      For(ref binding, ref iterable, ref body) => format!(
+        "\"kind\":\"for\",\"binding\":{},\"iterable\":{},\"body\":{}",
+        binding.to_json(),
+        iterable.to_json(),
+        body.to_json()
+      ),
+
+      Function(ref name, ref generics, ref params, ref return_type, ref body) => format!(
+        "\"kind\":\"function\",\"name\":{},\"generics\":[{}],\"params\":[{}],\"return\":{},\"body\":{}",
+        json_string(name),
+        generics.iter().map(|g| json_string(g)).collect::<Vec<String>>().join(","),
+        json_params(params),
+        json_type(return_type),
+        body.to_json()
+      ),
+
+      Signature(ref name, ref params, ref return_type) => format!(
+        "\"kind\":\"signature\",\"name\":{},\"params\":[{}],\"return\":{}",
+        json_string(name),
+        json_params(params),
+        json_type(return_type)
+      ),
+
+      Trait(ref name, ref body) => format!(
+        "\"kind\":\"trait\",\"name\":{},\"body\":{}",
+        json_string(name),
+        body.to_json()
+      ),
+
+      Implement(ref trait_name, ref t, ref body) => format!(
+        "\"kind\":\"implement\",\"trait\":{},\"type\":{},\"body\":{}",
+        trait_name.as_ref().map_or("null".to_string(), |n| json_string(n)),
+        json_type(t),
+        body.to_json()
+      ),
+
+      Struct(ref name, ref generics, ref body) => format!(
+        "\"kind\":\"struct\",\"name\":{},\"generics\":[{}],\"body\":{}",
+        json_string(name),
+        generics.iter().map(|g| json_string(g)).collect::<Vec<String>>().join(","),
+        body.to_json()
+      ),
+
+      Alias(ref name, ref t) => format!(
+        "\"kind\":\"alias\",\"name\":{},\"type\":{}",
+        json_string(name),
+        json_string(&t.to_string())
+      ),
+
+      Return(ref value) => format!(
+        "\"kind\":\"return\",\"value\":{}",
+        value.as_ref().map_or("null".to_string(), |v| v.to_json())
+      ),
+    };
+
+    format!("{{{},\"span\":{}}}", fields, json_span(&self.pos, &self.end))
+  }
+
+  fn to_sexp(&self) -> String {
+    use self::StatementNode::*;
+
+    match self.node {
+      Expression(ref expression) => expression.to_sexp(),
+
+      Variable(ref t, ref left, ref right) => {
+        let t = sexp_annotation(t);
+
+        match *right {
+          Some(ref right) => format!("(let {} {} {})", left.to_sexp(), t, right.to_sexp()),
+          None            => format!("(let {} {})", left.to_sexp(), t),
+        }
+      },
+
+      Constant(ref t, ref left, ref right) => {
+        let t = sexp_annotation(t);
+
+        format!("(const {} {} {})", left.to_sexp(), t, right.to_sexp())
+      },
+
+      Assignment(ref left, ref right) => format!("(set {} {})", left.to_sexp(), right.to_sexp()),
+
+      For(ref binding, ref iterable, ref body) => format!("(for {} {} {})", binding.to_sexp(), iterable.to_sexp(), body.to_sexp()),
+
+      Function(ref name, ref generics, ref params, ref return_type, ref body) => format!(
+        "(fun {}{} ({}) {} {})",
+        name,
+        sexp_generics(generics),
+        sexp_params(params),
+        sexp_type(return_type),
+        body.to_sexp()
+      ),
+
+      Signature(ref name, ref params, ref return_type) => format!("(fun {} ({}) {})", name, sexp_params(params), sexp_type(return_type)),
+
+      Trait(ref name, ref body) => format!("(trait {} {})", name, body.to_sexp()),
+
+      Implement(ref trait_name, ref t, ref body) => match *trait_name {
+        Some(ref trait_name) => format!("(implement {} {} {})", trait_name, sexp_type(t), body.to_sexp()),
+        None                 => format!("(implement {} {})", sexp_type(t), body.to_sexp()),
+      },
+
+      Struct(ref name, ref generics, ref body) => format!("(struct {}{} {})", name, sexp_generics(generics), body.to_sexp()),
+
+      Alias(ref name, ref t) => format!("(type {} {})", name, sexp_type(t)),
+
+      Return(ref value) => match *value {
+        Some(ref value) => format!("(return {})", value.to_sexp()),
+        None            => "(return)".to_string(),
+      },
+    }
+  }
+}
+
+impl<'e> Serialize for Expression<'e> {
+  fn to_json(&self) -> String {
+    use self::ExpressionNode::*;
+
+    let fields = match self.node {
+      Int(ref n)        => format!("\"kind\":\"int\",\"value\":{}", n),
+      Float(ref n)      => format!("\"kind\":\"float\",\"value\":{}", json_float(*n)),
+      String(ref s)     => format!("\"kind\":\"string\",\"value\":{}", json_string(s)),
+      Char(ref c)       => format!("\"kind\":\"char\",\"value\":{}", json_string(&c.to_string())),
+      Bool(ref b)       => format!("\"kind\":\"bool\",\"value\":{}", b),
+      Nil               => "\"kind\":\"nil\"".to_string(),
+      Identifier(ref n) => format!("\"kind\":\"identifier\",\"name\":{}", json_string(n)),
+
+      Block(ref statements) => format!("\"kind\":\"block\",\"statements\":{}", statements.to_json()),
+
+      Tuple(ref elements) => format!(
+        "\"kind\":\"tuple\",\"elements\":[{}]",
+        elements.iter().map(|e| e.to_json()).collect::<Vec<_>>().join(",")
+      ),
+
+      List(ref elements) => format!(
+        "\"kind\":\"list\",\"elements\":[{}]",
+        elements.iter().map(|e| e.to_json()).collect::<Vec<_>>().join(",")
+      ),
+
+      Fields(ref names) => format!(
+        "\"kind\":\"fields\",\"names\":[{}]",
+        names.iter().map(|n| n.to_json()).collect::<Vec<_>>().join(",")
+      ),
+
+      Field(ref value, ref name) => format!(
+        "\"kind\":\"field\",\"value\":{},\"name\":{}",
+        value.to_json(),
+        json_string(name)
+      ),
+
+      Cast(ref value, ref t) => format!(
+        "\"kind\":\"cast\",\"value\":{},\"type\":{}",
+        value.to_json(),
+        json_string(&t.to_string())
+      ),
+
+      Call(ref callee, ref arguments) => format!(
+        "\"kind\":\"call\",\"callee\":{},\"arguments\":[{}]",
+        callee.to_json(),
+        arguments.iter().map(|a| a.to_json()).collect::<Vec<_>>().join(",")
+      ),
+
+      Binary(ref left, ref op, ref right) => format!(
+        "\"kind\":\"binary\",\"operator\":{},\"left\":{},\"right\":{}",
+        json_string(op.as_str()),
+        left.to_json(),
+        right.to_json()
+      ),
+
+      Unary(ref op, ref operand) => format!(
+        "\"kind\":\"unary\",\"operator\":{},\"operand\":{}",
+        json_string(op.as_str()),
+        operand.to_json()
+      ),
+
+      If(ref condition, ref body, ref otherwise) => format!(
+        "\"kind\":\"if\",\"condition\":{},\"body\":{},\"else\":{}",
+        condition.to_json(),
+        body.to_json(),
+        otherwise.as_ref().map_or("null".to_string(), |e| e.to_json())
+      ),
+
+      Range(ref start, ref end, inclusive, ref step) => format!(
+        "\"kind\":\"range\",\"start\":{},\"end\":{},\"inclusive\":{},\"step\":{}",
+        start.to_json(),
+        end.to_json(),
+        inclusive,
+        step.as_ref().map_or("null".to_string(), |s| s.to_json())
+      ),
+
+      EOF => "\"kind\":\"eof\"".to_string(),
+    };
+
+    format!("{{{},\"span\":{}}}", fields, json_span(&self.pos, &self.pos))
+  }
+
+  fn to_sexp(&self) -> String {
+    use self::ExpressionNode::*;
+
+    match self.node {
+      Int(ref n)        => n.to_string(),
+      Float(ref n)      => json_float(*n),
+      String(ref s)     => json_string(s),
+      Char(ref c)       => escape(&c.to_string(), '\''),
+      Bool(ref b)       => b.to_string(),
+      Nil               => "nil".to_string(),
+      Identifier(ref n) => n.clone(),
+
+      Block(ref statements) => {
+        let mut sexp = "(block".to_string();
+
+        for statement in statements {
+          sexp.push(' ');
+          sexp.push_str(&statement.to_sexp())
+        }
+
+        sexp + ")"
+      },
+
+      Binary(ref left, ref op, ref right) => format!("({} {} {})", op, left.to_sexp(), right.to_sexp()),
+      Unary(ref op, ref operand)          => format!("({} {})", op, operand.to_sexp()),
+
+      Tuple(ref elements) => sexp_list("tuple", elements),
+
+      List(ref elements)  => sexp_list("list", elements),
+      Fields(ref names)   => sexp_list("fields", names),
+
+      Cast(ref value, ref t)     => format!("(as {} {})", value.to_sexp(), sexp_type(t)),
+      Field(ref value, ref name) => format!("(. {} {})", value.to_sexp(), name),
+
+      Call(ref callee, ref arguments) => {
+        let mut sexp = format!("(call {}", callee.to_sexp());
+
+        for argument in arguments {
+          sexp.push(' ');
+          sexp.push_str(&argument.to_sexp())
+        }
+
+        sexp + ")"
+      },
+
+      If(ref condition, ref body, ref otherwise) => match *otherwise {
+        Some(ref otherwise) => format!("(if {} {} {})", condition.to_sexp(), body.to_sexp(), otherwise.to_sexp()),
+        None                => format!("(if {} {})", condition.to_sexp(), body.to_sexp()),
+      },
+
+      Range(ref start, ref end, inclusive, ref step) => {
+        let range = format!("({} {} {}", if inclusive { "..=" } else { ".." }, start.to_sexp(), end.to_sexp());
+
+        match *step {
+          Some(ref step) => format!("{} {})", range, step.to_sexp()),
+          None           => range + ")",
+        }
+      },
+
+      EOF => "eof".to_string(),
+    }
+  }
+}
+
+
+
+// where values of type `any` have to be checked at runtime, and against what
+pub fn checks_to_json(checks: &[(TokenElement, Type)]) -> String {
+  format!(
+    "[{}]",
+    checks.iter().map(|&(ref pos, ref t)| format!("{{\"type\":{},\"span\":{}}}", json_type(t), json_span(pos, pos))).collect::<Vec<String>>().join(",")
+  )
+}
+
+fn sexp_list(head: &str, expressions: &[Expression]) -> String {
+  let mut sexp = format!("({}", head);
+
+  for expression in expressions {
+    sexp.push(' ');
+    sexp.push_str(&expression.to_sexp())
+  }
+
+  sexp + ")"
+}
+
+fn position(pos: &TokenElement) -> Option<(usize, (usize, usize))> {
+  match *pos {
+    TokenElement::Pos(line, slice) => Some((line.0, slice)),
+    TokenElement::Ref(token)       => Some((token.line.0, token.slice)),
+    _                              => None,
+  }
+}
+
+// from the start of the first token to the end of the last, which may be on a later line
+fn json_span(start: &TokenElement, end: &TokenElement) -> String {
+  match (position(start), position(end)) {
+    (Some((line, slice)), Some((end_line, end_slice))) => format!(
+      "{{\"line\":{},\"start\":{},\"end_line\":{},\"end\":{}}}",
+      line, slice.0, end_line, end_slice.1
+    ),
+
+    _ => "null".to_string(),
+  }
+}
+
+// an untyped `self` shows up as `_`, like a declaration without a type
+fn sexp_params(params: &[(String, Type)]) -> String {
+  params.iter().map(|&(ref param, ref t)| format!("({} {})", param, sexp_annotation(t))).collect::<Vec<String>>().join(" ")
+}
+
+fn sexp_annotation(t: &Type) -> String {
+  if *t == Type::Nil {
+    "_".to_string()
+  } else {
+    sexp_type(t)
+  }
+}
+
+// types as nested lists, `(tuple int str)` or `(fun (int) bool)`, rather than their source syntax
+fn sexp_type(t: &Type) -> String {
+  use self::Type::*;
+
+  let all = |types: &[Type]| types.iter().map(sexp_type).collect::<Vec<_>>().join(" ");
+
+  match *t {
+    Range(ref t)     => format!("(range {})", sexp_type(t)),
+    List(ref t)      => format!("(list {})", sexp_type(t)),
+    Optional(ref t)  => format!("(optional {})", sexp_type(t)),
+    Tuple(ref types) => format!("(tuple {})", all(types)),
+
+    Fun(ref params, ref return_type) => format!("(fun ({}) {})", all(params), sexp_type(return_type)),
+    Instance(ref name, ref types)    => format!("(instance {} {})", name, all(types)),
+    Generic(ref names, ref t)        => format!("(generic ({}) {})", names.join(" "), sexp_type(t)),
+
+    ref t => t.to_string(),
+  }
+}
+
+fn json_params(params: &[(String, Type)]) -> String {
+  params.iter().map(|&(ref param, ref t)| format!("{{\"name\":{},\"type\":{}}}", json_string(param), json_type(t))).collect::<Vec<String>>().join(",")
+}
+
+// `(generics T U)` right after the name, nothing when there aren't any
+fn sexp_generics(generics: &[String]) -> String {
+  if generics.is_empty() {
+    String::new()
+  } else {
+    format!(" (generics {})", generics.join(" "))
+  }
+}
+
+fn json_type(t: &Type) -> String {
+  if *t == Type::Nil {
+    "null".to_string()
+  } else {
+    json_string(&format!("{}", t))
+  }
+}
+
+// JSON has no infinities, and whole floats keep their fraction to stay floats
+fn json_float(n: f64) -> String {
+  if !n.is_finite() {
+    "null".to_string()
+  } else if n.fract() == 0.0 && n.abs() < 1e16 {
+    format!("{:.1}", n)
+  } else {
+    n.to_string()
+  }
+}
+
+fn json_string(s: &str) -> String {
+  escape(s, '"')
+}
+
+// quoted with the given delimiter, which s-expression chars share with strings
+fn escape(s: &str, delimiter: char) -> String {
+  let mut escaped = delimiter.to_string();
+
+  for c in s.chars() {
+    match c {
+      c if c == delimiter    => { escaped.push('\\'); escaped.push(c) },
+      '\\'                   => escaped.push_str("\\\\"),
+      '\n'                   => escaped.push_str("\\n"),
+      '\r'                   => escaped.push_str("\\r"),
+      '\t'                   => escaped.push_str("\\t"),
+      c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+      c                      => escaped.push(c),
+    }
+  }
+
+  escaped.push(delimiter);
+
+  escaped
+}
@@ -0,0 +1,82 @@
+use super::*;
+
+use super::super::source::Source;
+
+fn assert_lossless(content: &str) {
+  assert_eq!(::parse("test.lait", content, |_, syntax, _| syntax.text()).expect("source doesn't parse"), content)
+}
+
+#[test]
+fn lossless_lf() {
+  assert_lossless("hp := 10\n\nif hp > 5 {\n  hp = hp - 1\n}\n")
+}
+
+#[test]
+fn lossless_crlf() {
+  assert_lossless("hp := 10\r\n\r\nfun heal(n: int) {\r\n  hp = hp + n\r\n}\r\n")
+}
+
+#[test]
+fn lossless_comments() {
+  assert_lossless("-- leading\nhp := 10 -- trailing\n---\nblock\ncomment\n---\nmp := 2\n")
+}
+
+#[test]
+fn lossless_strings() {
+  assert_lossless("name := \"a \\\"quoted\\\" -- not a comment\\n\"\nc := '\\''\n")
+}
+
+fn parse_with<F: Fn(&Source, Vec<Statement>) -> String>(content: &str, f: F) -> String {
+  ::parse("test.lait", content, |source, _, ast| f(source, ast)).expect("source doesn't parse")
+}
+
+#[test]
+fn sexp_escapes_chars_and_nests_types() {
+  let sexp = parse_with("c := '\\''\nfun f<T>(g: fun(int) -> bool) -> (int, str?) {\n  return 1, nil\n}\n", |_, ast| ast.to_sexp());
+
+  assert_eq!(
+    sexp,
+    "(let c _ '\\'')\n(fun f (generics T) ((g (fun (int) bool))) (tuple int (optional str)) (block (return (tuple 1 nil))))"
+  )
+}
+
+#[test]
+fn statement_spans_cover_the_whole_statement() {
+  let json = parse_with("x: int = 1 + 2\nfun f() {\n  x = 3\n}\n", |_, ast| ast.to_json());
+
+  assert!(json.contains("\"kind\":\"variable\""));
+  assert!(json.ends_with("\"span\":{\"line\":2,\"start\":1,\"end_line\":4,\"end\":1}}]"));
+  assert!(json.contains("}},\"span\":{\"line\":1,\"start\":1,\"end_line\":1,\"end\":14}}"))
+}
+
+#[test]
+fn statement_spans_survive_folding() {
+  let json = parse_with("x := 1 + 2\nfun f() {\n  y := 3 * 4\n}\n", |source, ast| {
+    ConstantFolder::new(source).fold_statements(ast).expect("source doesn't fold").to_json()
+  });
+
+  assert!(json.contains("\"value\":3"));
+  assert!(json.contains("\"span\":{\"line\":1,\"start\":1,\"end_line\":1,\"end\":10}}"));
+  assert!(json.contains("\"span\":{\"line\":3,\"start\":3,\"end_line\":3,\"end\":12}}"));
+  assert!(json.ends_with("\"span\":{\"line\":2,\"start\":1,\"end_line\":4,\"end\":1}}]"))
+}
+
+#[test]
+fn casts_compare_with_less_than() {
+  let sexp = parse_with("x := 3\nb := x as int < 5\nif x as float < 5.0 {\n}\n", |_, ast| ast.to_sexp());
+
+  assert_eq!(sexp, "(let x _ 3)\n(let b _ (< (as x int) 5))\n(if (< (as x float) 5.0) (block))")
+}
+
+#[test]
+fn directives_come_from_line_comments() {
+  let content = "-- lait: strict\nx := 1 -- lait: allow(naming)\n---\n-- lait: deny(naming)\n---\ns := \"-- lait: allow(deep_nesting)\"\n";
+
+  assert_eq!(
+    ::parse("test.lait", content, |_, syntax, _| syntax.directives()).expect("source doesn't parse"),
+    vec![
+      Directive { line: 1, text: "strict".to_string(),        trailing: false },
+      Directive { line: 2, text: "allow(naming)".to_string(), trailing: true  },
+    ]
+  )
+}
@@ -0,0 +1,496 @@
+use super::*;
+use super::visitor::Type;
+
+use std::rc::Rc;
+
+// read-only traversal, override the nodes you care about and call the
+// matching free function to keep descending
+pub trait Walk<'w> {
+  fn walk_statements(&mut self, statements: &'w [Statement<'w>]) -> Result<(), ()> {
+    for statement in statements {
+      self.walk_statement(statement)?
+    }
+
+    Ok(())
+  }
+
+  fn walk_statement(&mut self, statement: &'w Statement<'w>) -> Result<(), ()> {
+    walk_statement(self, statement)
+  }
+
+  fn walk_expression(&mut self, expression: &'w Expression<'w>) -> Result<(), ()> {
+    walk_expression(self, expression)
+  }
+
+
+
+  fn walk_expression_statement(&mut self, _statement: &'w Statement<'w>, expression: &'w Expression<'w>) -> Result<(), ()> {
+    self.walk_expression(expression)
+  }
+
+  fn walk_variable(&mut self, _statement: &'w Statement<'w>, _t: &'w Type, left: &'w Expression<'w>, right: Option<&'w Expression<'w>>) -> Result<(), ()> {
+    self.walk_expression(left)?;
+
+    if let Some(right) = right {
+      self.walk_expression(right)?
+    }
+
+    Ok(())
+  }
+
+  fn walk_constant(&mut self, _statement: &'w Statement<'w>, _t: &'w Type, left: &'w Expression<'w>, right: &'w Expression<'w>) -> Result<(), ()> {
+    self.walk_expression(left)?;
+    self.walk_expression(right)
+  }
+
+  fn walk_assignment(&mut self, _statement: &'w Statement<'w>, left: &'w Expression<'w>, right: &'w Expression<'w>) -> Result<(), ()> {
+    self.walk_expression(left)?;
+    self.walk_expression(right)
+  }
+
+  fn walk_for(&mut self, _statement: &'w Statement<'w>, binding: &'w Expression<'w>, iterable: &'w Expression<'w>, body: &'w Expression<'w>) -> Result<(), ()> {
+    self.walk_expression(binding)?;
+    self.walk_expression(iterable)?;
+    self.walk_expression(body)
+  }
+
+  fn walk_function(&mut self, _statement: &'w Statement<'w>, _name: &'w str, _generics: &'w [String], _params: &'w [(String, Type)], _return_type: &'w Type, body: &'w Expression<'w>) -> Result<(), ()> {
+    self.walk_expression(body)
+  }
+
+  fn walk_struct(&mut self, _statement: &'w Statement<'w>, _name: &'w str, _generics: &'w [String], body: &'w Expression<'w>) -> Result<(), ()> {
+    self.walk_expression(body)
+  }
+
+  fn walk_signature(&mut self, _statement: &'w Statement<'w>, _name: &'w str, _params: &'w [(String, Type)], _return_type: &'w Type) -> Result<(), ()> {
+    Ok(())
+  }
+
+  fn walk_trait(&mut self, _statement: &'w Statement<'w>, _name: &'w str, body: &'w Expression<'w>) -> Result<(), ()> {
+    self.walk_expression(body)
+  }
+
+  fn walk_implement(&mut self, _statement: &'w Statement<'w>, _trait_name: Option<&'w str>, _t: &'w Type, body: &'w Expression<'w>) -> Result<(), ()> {
+    self.walk_expression(body)
+  }
+
+  fn walk_alias(&mut self, _statement: &'w Statement<'w>, _name: &'w str, _t: &'w Type) -> Result<(), ()> {
+    Ok(())
+  }
+
+  fn walk_return(&mut self, _statement: &'w Statement<'w>, value: Option<&'w Expression<'w>>) -> Result<(), ()> {
+    if let Some(value) = value {
+      self.walk_expression(value)?
+    }
+
+    Ok(())
+  }
+
+
+
+  // `Int`, `Float`, `String`, `Char`, `Bool`, `Nil` and `EOF`
+  fn walk_literal(&mut self, _expression: &'w Expression<'w>) -> Result<(), ()> {
+    Ok(())
+  }
+
+  fn walk_identifier(&mut self, _expression: &'w Expression<'w>, _name: &'w str) -> Result<(), ()> {
+    Ok(())
+  }
+
+  fn walk_binary(&mut self, _expression: &'w Expression<'w>, left: &'w Expression<'w>, _op: &'w Operator, right: &'w Expression<'w>) -> Result<(), ()> {
+    self.walk_expression(left)?;
+    self.walk_expression(right)
+  }
+
+  fn walk_unary(&mut self, _expression: &'w Expression<'w>, _op: &'w Operator, operand: &'w Expression<'w>) -> Result<(), ()> {
+    self.walk_expression(operand)
+  }
+
+  fn walk_block(&mut self, _expression: &'w Expression<'w>, statements: &'w [Statement<'w>]) -> Result<(), ()> {
+    self.walk_statements(statements)
+  }
+
+  fn walk_if(&mut self, _expression: &'w Expression<'w>, condition: &'w Expression<'w>, body: &'w Expression<'w>, otherwise: Option<&'w Expression<'w>>) -> Result<(), ()> {
+    self.walk_expression(condition)?;
+    self.walk_expression(body)?;
+
+    if let Some(otherwise) = otherwise {
+      self.walk_expression(otherwise)?
+    }
+
+    Ok(())
+  }
+
+  fn walk_range(&mut self, _expression: &'w Expression<'w>, start: &'w Expression<'w>, end: &'w Expression<'w>, _inclusive: bool, step: Option<&'w Expression<'w>>) -> Result<(), ()> {
+    self.walk_expression(start)?;
+    self.walk_expression(end)?;
+
+    if let Some(step) = step {
+      self.walk_expression(step)?
+    }
+
+    Ok(())
+  }
+
+  fn walk_tuple(&mut self, _expression: &'w Expression<'w>, elements: &'w [Expression<'w>]) -> Result<(), ()> {
+    for element in elements {
+      self.walk_expression(element)?
+    }
+
+    Ok(())
+  }
+
+  fn walk_list(&mut self, _expression: &'w Expression<'w>, elements: &'w [Expression<'w>]) -> Result<(), ()> {
+    for element in elements {
+      self.walk_expression(element)?
+    }
+
+    Ok(())
+  }
+
+  fn walk_fields(&mut self, _expression: &'w Expression<'w>, names: &'w [Expression<'w>]) -> Result<(), ()> {
+    for name in names {
+      self.walk_expression(name)?
+    }
+
+    Ok(())
+  }
+
+  fn walk_cast(&mut self, _expression: &'w Expression<'w>, value: &'w Expression<'w>, _t: &'w Type) -> Result<(), ()> {
+    self.walk_expression(value)
+  }
+
+  fn walk_field(&mut self, _expression: &'w Expression<'w>, value: &'w Expression<'w>, _name: &'w str) -> Result<(), ()> {
+    self.walk_expression(value)
+  }
+
+  fn walk_call(&mut self, _expression: &'w Expression<'w>, callee: &'w Expression<'w>, arguments: &'w [Expression<'w>]) -> Result<(), ()> {
+    self.walk_expression(callee)?;
+
+    for argument in arguments {
+      self.walk_expression(argument)?
+    }
+
+    Ok(())
+  }
+}
+
+pub fn walk_statement<'w, W: Walk<'w> + ?Sized>(walker: &mut W, statement: &'w Statement<'w>) -> Result<(), ()> {
+  use self::StatementNode::*;
+
+  match statement.node {
+    Expression(ref expression)               => walker.walk_expression_statement(statement, expression),
+    Variable(ref t, ref left, ref right)     => walker.walk_variable(statement, t, left, right.as_ref()),
+    Constant(ref t, ref left, ref right)     => walker.walk_constant(statement, t, left, right),
+    Assignment(ref left, ref right)          => walker.walk_assignment(statement, left, right),
+    For(ref binding, ref iterable, ref body) => walker.walk_for(statement, binding, iterable, body),
+    Return(ref value)                        => walker.walk_return(statement, value.as_ref()),
+    Struct(ref name, ref generics, ref body) => walker.walk_struct(statement, name, generics, body),
+    Alias(ref name, ref t)                   => walker.walk_alias(statement, name, t),
+    Trait(ref name, ref body)                => walker.walk_trait(statement, name, body),
+
+    Function(ref name, ref generics, ref params, ref return_type, ref body) => walker.walk_function(statement, name, generics, params, return_type, body),
+    Signature(ref name, ref params, ref return_type)                        => walker.walk_signature(statement, name, params, return_type),
+    Implement(ref trait_name, ref t, ref body)                              => walker.walk_implement(statement, trait_name.as_ref().map(|n| n.as_str()), t, body),
+  }
+}
+
+pub fn walk_expression<'w, W: Walk<'w> + ?Sized>(walker: &mut W, expression: &'w Expression<'w>) -> Result<(), ()> {
+  use self::ExpressionNode::*;
+
+  match expression.node {
+    Identifier(ref name)                => walker.walk_identifier(expression, name),
+    Binary(ref left, ref op, ref right) => walker.walk_binary(expression, left, op, right),
+    Unary(ref op, ref operand)          => walker.walk_unary(expression, op, operand),
+    Block(ref statements)               => walker.walk_block(expression, statements),
+    Tuple(ref elements)                 => walker.walk_tuple(expression, elements),
+    List(ref elements)                  => walker.walk_list(expression, elements),
+    Fields(ref names)                   => walker.walk_fields(expression, names),
+    Call(ref callee, ref arguments)     => walker.walk_call(expression, callee, arguments),
+    Cast(ref value, ref t)              => walker.walk_cast(expression, value, t),
+
+    If(ref condition, ref body, ref otherwise)     => walker.walk_if(expression, condition, body, otherwise.as_ref().map(|e| &**e)),
+    Range(ref start, ref end, inclusive, ref step) => walker.walk_range(expression, start, end, inclusive, step.as_ref().map(|s| &**s)),
+
+    Field(ref value, ref name)          => walker.walk_field(expression, value, name),
+
+    Int(_) | Float(_) | String(_) | Char(_) | Bool(_) | Nil | EOF => walker.walk_literal(expression),
+  }
+}
+
+
+
+// owning traversal that rebuilds the tree, for desugaring and rewriting passes
+pub trait Fold<'f> {
+  fn fold_statements(&mut self, statements: Vec<Statement<'f>>) -> Result<Vec<Statement<'f>>, ()> {
+    let mut folded = Vec::new();
+
+    for statement in statements {
+      folded.push(self.fold_statement(statement)?)
+    }
+
+    Ok(folded)
+  }
+
+  fn fold_statement(&mut self, statement: Statement<'f>) -> Result<Statement<'f>, ()> {
+    fold_statement(self, statement)
+  }
+
+  fn fold_expression(&mut self, expression: Expression<'f>) -> Result<Expression<'f>, ()> {
+    fold_expression(self, expression)
+  }
+
+  fn fold_type(&mut self, t: Type) -> Result<Type, ()> {
+    Ok(t)
+  }
+
+
+
+  fn fold_expression_statement(&mut self, expression: Expression<'f>, pos: TokenElement<'f>) -> Result<Statement<'f>, ()> {
+    Ok(Statement::new(StatementNode::Expression(self.fold_expression(expression)?), pos))
+  }
+
+  fn fold_variable(&mut self, t: Type, left: Expression<'f>, right: Option<Expression<'f>>, pos: TokenElement<'f>) -> Result<Statement<'f>, ()> {
+    let t     = self.fold_type(t)?;
+    let left  = self.fold_expression(left)?;
+    let right = match right {
+      Some(right) => Some(self.fold_expression(right)?),
+      None        => None,
+    };
+
+    Ok(Statement::new(StatementNode::Variable(t, left, right), pos))
+  }
+
+  fn fold_constant(&mut self, t: Type, left: Expression<'f>, right: Expression<'f>, pos: TokenElement<'f>) -> Result<Statement<'f>, ()> {
+    let t     = self.fold_type(t)?;
+    let left  = self.fold_expression(left)?;
+    let right = self.fold_expression(right)?;
+
+    Ok(Statement::new(StatementNode::Constant(t, left, right), pos))
+  }
+
+  fn fold_assignment(&mut self, left: Expression<'f>, right: Expression<'f>, pos: TokenElement<'f>) -> Result<Statement<'f>, ()> {
+    let left  = self.fold_expression(left)?;
+    let right = self.fold_expression(right)?;
+
+    Ok(Statement::new(StatementNode::Assignment(left, right), pos))
+  }
+
+  fn fold_for(&mut self, binding: Expression<'f>, iterable: Expression<'f>, body: Expression<'f>, pos: TokenElement<'f>) -> Result<Statement<'f>, ()> {
+    let binding  = self.fold_expression(binding)?;
+    let iterable = self.fold_expression(iterable)?;
+    let body     = self.fold_expression(body)?;
+
+    Ok(Statement::new(StatementNode::For(binding, iterable, body), pos))
+  }
+
+  fn fold_function(&mut self, name: String, generics: Vec<String>, params: Vec<(String, Type)>, return_type: Type, body: Expression<'f>, pos: TokenElement<'f>) -> Result<Statement<'f>, ()> {
+    let mut folded_params = Vec::new();
+
+    for (param, t) in params {
+      folded_params.push((param, self.fold_type(t)?))
+    }
+
+    let return_type = self.fold_type(return_type)?;
+    let body        = self.fold_expression(body)?;
+
+    Ok(Statement::new(StatementNode::Function(name, generics, folded_params, return_type, body), pos))
+  }
+
+  fn fold_struct(&mut self, name: String, generics: Vec<String>, body: Expression<'f>, pos: TokenElement<'f>) -> Result<Statement<'f>, ()> {
+    Ok(Statement::new(StatementNode::Struct(name, generics, self.fold_expression(body)?), pos))
+  }
+
+  fn fold_signature(&mut self, name: String, params: Vec<(String, Type)>, return_type: Type, pos: TokenElement<'f>) -> Result<Statement<'f>, ()> {
+    let mut folded_params = Vec::new();
+
+    for (param, t) in params {
+      folded_params.push((param, self.fold_type(t)?))
+    }
+
+    let return_type = self.fold_type(return_type)?;
+
+    Ok(Statement::new(StatementNode::Signature(name, folded_params, return_type), pos))
+  }
+
+  fn fold_trait(&mut self, name: String, body: Expression<'f>, pos: TokenElement<'f>) -> Result<Statement<'f>, ()> {
+    Ok(Statement::new(StatementNode::Trait(name, self.fold_expression(body)?), pos))
+  }
+
+  fn fold_implement(&mut self, trait_name: Option<String>, t: Type, body: Expression<'f>, pos: TokenElement<'f>) -> Result<Statement<'f>, ()> {
+    let t    = self.fold_type(t)?;
+    let body = self.fold_expression(body)?;
+
+    Ok(Statement::new(StatementNode::Implement(trait_name, t, body), pos))
+  }
+
+  fn fold_alias(&mut self, name: String, t: Type, pos: TokenElement<'f>) -> Result<Statement<'f>, ()> {
+    Ok(Statement::new(StatementNode::Alias(name, self.fold_type(t)?), pos))
+  }
+
+  fn fold_return(&mut self, value: Option<Expression<'f>>, pos: TokenElement<'f>) -> Result<Statement<'f>, ()> {
+    let value = match value {
+      Some(value) => Some(self.fold_expression(value)?),
+      None        => None,
+    };
+
+    Ok(Statement::new(StatementNode::Return(value), pos))
+  }
+
+
+
+  fn fold_literal(&mut self, expression: Expression<'f>) -> Result<Expression<'f>, ()> {
+    Ok(expression)
+  }
+
+  fn fold_identifier(&mut self, name: String, pos: TokenElement<'f>) -> Result<Expression<'f>, ()> {
+    Ok(Expression::new(ExpressionNode::Identifier(name), pos))
+  }
+
+  fn fold_binary(&mut self, left: Expression<'f>, op: Operator, right: Expression<'f>, pos: TokenElement<'f>) -> Result<Expression<'f>, ()> {
+    let left  = self.fold_expression(left)?;
+    let right = self.fold_expression(right)?;
+
+    Ok(Expression::new(ExpressionNode::Binary(Rc::new(left), op, Rc::new(right)), pos))
+  }
+
+  fn fold_unary(&mut self, op: Operator, operand: Expression<'f>, pos: TokenElement<'f>) -> Result<Expression<'f>, ()> {
+    Ok(Expression::new(ExpressionNode::Unary(op, Rc::new(self.fold_expression(operand)?)), pos))
+  }
+
+  fn fold_block(&mut self, statements: Vec<Statement<'f>>, pos: TokenElement<'f>) -> Result<Expression<'f>, ()> {
+    Ok(Expression::new(ExpressionNode::Block(self.fold_statements(statements)?), pos))
+  }
+
+  fn fold_if(&mut self, condition: Expression<'f>, body: Expression<'f>, otherwise: Option<Expression<'f>>, pos: TokenElement<'f>) -> Result<Expression<'f>, ()> {
+    let condition = self.fold_expression(condition)?;
+    let body      = self.fold_expression(body)?;
+    let otherwise = match otherwise {
+      Some(otherwise) => Some(Rc::new(self.fold_expression(otherwise)?)),
+      None            => None,
+    };
+
+    Ok(Expression::new(ExpressionNode::If(Rc::new(condition), Rc::new(body), otherwise), pos))
+  }
+
+  fn fold_range(&mut self, start: Expression<'f>, end: Expression<'f>, inclusive: bool, step: Option<Expression<'f>>, pos: TokenElement<'f>) -> Result<Expression<'f>, ()> {
+    let start = self.fold_expression(start)?;
+    let end   = self.fold_expression(end)?;
+    let step  = match step {
+      Some(step) => Some(Rc::new(self.fold_expression(step)?)),
+      None       => None,
+    };
+
+    Ok(Expression::new(ExpressionNode::Range(Rc::new(start), Rc::new(end), inclusive, step), pos))
+  }
+
+  fn fold_tuple(&mut self, elements: Vec<Expression<'f>>, pos: TokenElement<'f>) -> Result<Expression<'f>, ()> {
+    let mut folded = Vec::new();
+
+    for element in elements {
+      folded.push(self.fold_expression(element)?)
+    }
+
+    Ok(Expression::new(ExpressionNode::Tuple(folded), pos))
+  }
+
+  fn fold_list(&mut self, elements: Vec<Expression<'f>>, pos: TokenElement<'f>) -> Result<Expression<'f>, ()> {
+    let mut folded = Vec::new();
+
+    for element in elements {
+      folded.push(self.fold_expression(element)?)
+    }
+
+    Ok(Expression::new(ExpressionNode::List(folded), pos))
+  }
+
+  fn fold_fields(&mut self, names: Vec<Expression<'f>>, pos: TokenElement<'f>) -> Result<Expression<'f>, ()> {
+    let mut folded = Vec::new();
+
+    for name in names {
+      folded.push(self.fold_expression(name)?)
+    }
+
+    Ok(Expression::new(ExpressionNode::Fields(folded), pos))
+  }
+
+  fn fold_cast(&mut self, value: Expression<'f>, t: Type, pos: TokenElement<'f>) -> Result<Expression<'f>, ()> {
+    let value = self.fold_expression(value)?;
+    let t     = self.fold_type(t)?;
+
+    Ok(Expression::new(ExpressionNode::Cast(Rc::new(value), t), pos))
+  }
+
+  fn fold_field(&mut self, value: Expression<'f>, name: String, pos: TokenElement<'f>) -> Result<Expression<'f>, ()> {
+    Ok(Expression::new(ExpressionNode::Field(Rc::new(self.fold_expression(value)?), name), pos))
+  }
+
+  fn fold_call(&mut self, callee: Expression<'f>, arguments: Vec<Expression<'f>>, pos: TokenElement<'f>) -> Result<Expression<'f>, ()> {
+    let callee = self.fold_expression(callee)?;
+
+    let mut folded = Vec::new();
+
+    for argument in arguments {
+      folded.push(self.fold_expression(argument)?)
+    }
+
+    Ok(Expression::new(ExpressionNode::Call(Rc::new(callee), folded), pos))
+  }
+}
+
+pub fn fold_statement<'f, F: Fold<'f> + ?Sized>(folder: &mut F, statement: Statement<'f>) -> Result<Statement<'f>, ()> {
+  use self::StatementNode::*;
+
+  let pos = statement.pos;
+  let end = statement.end;
+
+  // the folds rebuild statements from their start, so the end carries over here
+  let mut folded = match statement.node {
+    Expression(expression)       => folder.fold_expression_statement(expression, pos),
+    Variable(t, left, right)     => folder.fold_variable(t, left, right, pos),
+    Constant(t, left, right)     => folder.fold_constant(t, left, right, pos),
+    Assignment(left, right)      => folder.fold_assignment(left, right, pos),
+    For(binding, iterable, body) => folder.fold_for(binding, iterable, body, pos),
+    Return(value)                => folder.fold_return(value, pos),
+    Struct(name, generics, body) => folder.fold_struct(name, generics, body, pos),
+    Alias(name, t)               => folder.fold_alias(name, t, pos),
+    Trait(name, body)            => folder.fold_trait(name, body, pos),
+
+    Function(name, generics, params, return_type, body) => folder.fold_function(name, generics, params, return_type, body, pos),
+    Signature(name, params, return_type)                => folder.fold_signature(name, params, return_type, pos),
+    Implement(trait_name, t, body)                      => folder.fold_implement(trait_name, t, body, pos),
+  }?;
+
+  folded.end = end;
+
+  Ok(folded)
+}
+
+pub fn fold_expression<'f, F: Fold<'f> + ?Sized>(folder: &mut F, expression: Expression<'f>) -> Result<Expression<'f>, ()> {
+  use self::ExpressionNode::*;
+
+  let pos = expression.pos;
+
+  match expression.node {
+    Identifier(name)        => folder.fold_identifier(name, pos),
+    Binary(left, op, right) => folder.fold_binary(unwrap_rc(left), op, unwrap_rc(right), pos),
+    Unary(op, operand)      => folder.fold_unary(op, unwrap_rc(operand), pos),
+    Block(statements)       => folder.fold_block(statements, pos),
+    Tuple(elements)         => folder.fold_tuple(elements, pos),
+    List(elements)          => folder.fold_list(elements, pos),
+    Fields(names)           => folder.fold_fields(names, pos),
+    Call(callee, arguments) => folder.fold_call(unwrap_rc(callee), arguments, pos),
+    Cast(value, t)          => folder.fold_cast(unwrap_rc(value), t, pos),
+    Field(value, name)      => folder.fold_field(unwrap_rc(value), name, pos),
+
+    If(condition, body, otherwise)     => folder.fold_if(unwrap_rc(condition), unwrap_rc(body), otherwise.map(unwrap_rc), pos),
+    Range(start, end, inclusive, step) => folder.fold_range(unwrap_rc(start), unwrap_rc(end), inclusive, step.map(unwrap_rc), pos),
+
+    node => folder.fold_literal(Expression::new(node, pos)),
+  }
+}
+
+// takes the node out of its `Rc` without cloning when nothing else holds it
+pub fn unwrap_rc<'f>(expression: Rc<Expression<'f>>) -> Expression<'f> {
+  Rc::try_unwrap(expression).unwrap_or_else(|shared| (*shared).clone())
+}
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use super::Type;
+
+use std::rc::Rc;
+
+
+
+// the type namespace, `type Name = T` declarations by name
+#[derive(Clone, Debug)]
+pub struct AliasTab {
+  pub aliases: HashMap<String, Type>,
+}
+
+impl AliasTab {
+  pub fn new() -> Self {
+    AliasTab {
+      aliases: HashMap::new(),
+    }
+  }
+
+  // false when the name is taken
+  pub fn add_alias(&mut self, name: &str, t: Type) -> bool {
+    if self.aliases.contains_key(name) {
+      false
+    } else {
+      self.aliases.insert(name.to_string(), t);
+      true
+    }
+  }
+
+  // replaces every alias in `t` by what it stands for, an `Err` holds the names that form a cycle
+  pub fn expand(&self, t: &Type) -> Result<Type, Vec<String>> {
+    self.expand_internal(t, &mut Vec::new())
+  }
+
+  fn expand_internal(&self, t: &Type, stack: &mut Vec<String>) -> Result<Type, Vec<String>> {
+    use self::Type::*;
+
+    let expanded = match *t {
+      Id(ref name) => match self.aliases.get(name) {
+        Some(aliased) => {
+          if let Some(start) = stack.iter().position(|n| n == name) {
+            return Err(stack[start ..].to_vec())
+          }
+
+          stack.push(name.clone());
+
+          let expanded = self.expand_internal(aliased, stack)?;
+
+          stack.pop();
+
+          expanded
+        },
+
+        None => t.clone(),
+      },
+
+      Range(ref t)    => Range(Rc::new(self.expand_internal(t, stack)?)),
+      List(ref t)     => List(Rc::new(self.expand_internal(t, stack)?)),
+      Optional(ref t) => Optional(Rc::new(self.expand_internal(t, stack)?)),
+
+      Instance(ref name, ref types) => {
+        let mut expanded = Vec::new();
+
+        for t in types {
+          expanded.push(self.expand_internal(t, stack)?)
+        }
+
+        Instance(name.clone(), expanded)
+      },
+
+      Tuple(ref types) => {
+        let mut expanded = Vec::new();
+
+        for t in types {
+          expanded.push(self.expand_internal(t, stack)?)
+        }
+
+        Tuple(expanded)
+      },
+
+      Fun(ref params, ref return_type) => {
+        let mut expanded = Vec::new();
+
+        for t in params {
+          expanded.push(self.expand_internal(t, stack)?)
+        }
+
+        Fun(expanded, Rc::new(self.expand_internal(return_type, stack)?))
+      },
+
+      _ => t.clone(),
+    };
+
+    Ok(expanded)
+  }
+}
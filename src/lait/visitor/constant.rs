@@ -0,0 +1,329 @@
+use super::super::error::Response::Wrong;
+
+use std::rc::Rc;
+use std::collections::HashMap;
+
+use super::*;
+
+// a constant's value as it gets inlined, with the declared type when there is one
+type Inlined = (ExpressionNode<'static>, Type);
+
+// evaluates operations on literals ahead of time, reporting the ones that
+// would overflow or divide by zero at the operation's span, and inlines
+// `name :: value` constants whose values fold down to literals
+pub struct ConstantFolder<'c> {
+  pub source:    &'c Source,
+  pub constants: Vec<HashMap<String, Option<Inlined>>>, // `None` where a name is hidden by something that isn't inlined
+}
+
+impl<'c> ConstantFolder<'c> {
+  pub fn new(source: &'c Source) -> Self {
+    ConstantFolder {
+      source,
+      constants: vec!(HashMap::new()),
+    }
+  }
+
+  fn push_scope(&mut self) {
+    self.constants.push(HashMap::new())
+  }
+
+  fn pop_scope(&mut self) {
+    self.constants.pop();
+  }
+
+  fn hide(&mut self, name: &str) {
+    self.constants.last_mut().unwrap().insert(name.to_string(), None);
+  }
+
+  // every name a declaration pattern binds
+  fn hide_pattern(&mut self, pattern: &Expression) {
+    match pattern.node {
+      ExpressionNode::Identifier(ref name) => self.hide(name),
+
+      ExpressionNode::Tuple(ref parts) | ExpressionNode::List(ref parts) | ExpressionNode::Fields(ref parts) => for part in parts {
+        self.hide_pattern(part)
+      },
+
+      _ => (),
+    }
+  }
+
+  fn lookup(&self, name: &str) -> Option<&Inlined> {
+    for scope in self.constants.iter().rev() {
+      if let Some(constant) = scope.get(name) {
+        return constant.as_ref()
+      }
+    }
+
+    None
+  }
+
+  // the literal a use of the constant turns into, typed ones only where a cast or the literal itself keeps the declared type
+  fn inline<'e>(constant: &Inlined, pos: TokenElement<'e>) -> Option<Expression<'e>> {
+    let (ref node, ref t) = *constant;
+
+    let literal = Expression::new(node.clone(), pos.clone());
+
+    let own = match *node {
+      ExpressionNode::String(_) => Type::String,
+      ExpressionNode::Char(_)   => Type::Char,
+      ExpressionNode::Bool(_)   => Type::Bool,
+      _                         => Type::Nil,
+    };
+
+    if *t == Type::Nil || *t == own {
+      Some(literal)
+    } else if t.is_numeric() {
+      Some(Expression::new(ExpressionNode::Cast(Rc::new(literal), t.clone()), pos))
+    } else {
+      None
+    }
+  }
+
+  fn literal(node: &ExpressionNode) -> Option<ExpressionNode<'static>> {
+    use self::ExpressionNode::*;
+
+    let literal = match *node {
+      Int(n)        => Int(n),
+      Float(n)      => Float(n),
+      String(ref s) => String(s.clone()),
+      Char(c)       => Char(c),
+      Bool(b)       => Bool(b),
+      _             => return None,
+    };
+
+    Some(literal)
+  }
+
+  pub fn evaluate<'e>(&self, left: &ExpressionNode<'e>, op: &Operator, right: &ExpressionNode<'e>, pos: &TokenElement<'e>) -> Result<Option<ExpressionNode<'e>>, ()> {
+    use self::ExpressionNode::*;
+    use self::Operator::*;
+
+    let folded = match (left, right) {
+      (&Int(a), &Int(b)) => match *op {
+        Add  => Int(self.checked(a.checked_add(b), "addition overflows", pos)?),
+        Sub  => Int(self.checked(a.checked_sub(b), "subtraction overflows", pos)?),
+        Mul  => Int(self.checked(a.checked_mul(b), "multiplication overflows", pos)?),
+        Div  => Int(self.checked(a.checked_div(b), "division by zero", pos)?),
+        Mod  => Int(self.checked(a.checked_rem(b), "remainder by zero", pos)?),
+
+        Pow  => {
+          let exponent = self.checked(if b < 0 { None } else { Some(b) }, "negative integer exponent", pos)?;
+          let exponent = if exponent > u32::max_value() as i128 { None } else { Some(exponent as u32) };
+
+          Int(self.checked(exponent.and_then(|e| a.checked_pow(e)), "exponentiation overflows", pos)?)
+        },
+
+        ref op => return Ok(Self::compare(op, &a, &b)),
+      },
+
+      (&Float(a), &Float(b)) => return self.arithmetic(op, a, b, pos),
+      (&Int(a),   &Float(b)) => return self.arithmetic(op, a as f64, b, pos),
+      (&Float(a), &Int(b))   => return self.arithmetic(op, a, b as f64, pos),
+
+      (&String(ref a), &String(ref b)) => match *op {
+        Concat => String(format!("{}{}", a, b)),
+        ref op => return Ok(Self::compare(op, a, b)),
+      },
+
+      (&Char(ref a), &Char(ref b)) => return Ok(Self::compare(op, a, b)),
+
+      (&Bool(a), &Bool(b)) => match *op {
+        Eq  => Bool(a == b),
+        NEq => Bool(a != b),
+        _   => return Ok(None),
+      },
+
+      _ => return Ok(None),
+    };
+
+    Ok(Some(folded))
+  }
+
+  // ints mixed with floats are promoted to floats, results that aren't finite are reported like integer overflow
+  fn arithmetic<'e>(&self, op: &Operator, a: f64, b: f64, pos: &TokenElement) -> Result<Option<ExpressionNode<'e>>, ()> {
+    use self::ExpressionNode::*;
+    use self::Operator::*;
+
+    let (result, message) = match *op {
+      Add    => (a + b, "addition overflows"),
+      Sub    => (a - b, "subtraction overflows"),
+      Mul    => (a * b, "multiplication overflows"),
+      Div    => (a / b, if b == 0.0 { "division by zero" } else { "division overflows" }),
+      Mod    => (a % b, "remainder by zero"),
+      Pow    => (a.powf(b), "exponentiation overflows"),
+      Concat => return Ok(None),
+      ref op => return Ok(Self::compare(op, &a, &b)),
+    };
+
+    let finite = if result.is_finite() { Some(result) } else { None };
+
+    Ok(Some(Float(self.checked(finite, message, pos)?)))
+  }
+
+  fn compare<'e, T: PartialOrd>(op: &Operator, a: &T, b: &T) -> Option<ExpressionNode<'e>> {
+    use self::Operator::*;
+
+    let result = match *op {
+      Eq   => a == b,
+      NEq  => a != b,
+      Lt   => a < b,
+      Gt   => a > b,
+      LtEq => a <= b,
+      GtEq => a >= b,
+      _    => return None,
+    };
+
+    Some(ExpressionNode::Bool(result))
+  }
+
+  fn checked<T>(&self, result: Option<T>, message: &str, pos: &TokenElement) -> Result<T, ()> {
+    match result {
+      Some(value) => Ok(value),
+      None        => Err(
+        response!(
+          Wrong(format!("constant evaluation failed, {}", message)),
+          self.source.file,
+          pos
+        )
+      ),
+    }
+  }
+}
+
+impl<'c, 'f> Fold<'f> for ConstantFolder<'c> {
+  fn fold_constant(&mut self, t: Type, left: Expression<'f>, right: Expression<'f>, pos: TokenElement<'f>) -> Result<Statement<'f>, ()> {
+    let right = self.fold_expression(right)?;
+
+    self.hide_pattern(&left);
+
+    if let ExpressionNode::Identifier(ref name) = left.node {
+      if let Some(literal) = Self::literal(&right.node) {
+        self.constants.last_mut().unwrap().insert(name.clone(), Some((literal, t.clone())));
+      }
+    }
+
+    Ok(Statement::new(StatementNode::Constant(t, left, right), pos))
+  }
+
+  // the names being declared or assigned stay as they are
+  fn fold_variable(&mut self, t: Type, left: Expression<'f>, right: Option<Expression<'f>>, pos: TokenElement<'f>) -> Result<Statement<'f>, ()> {
+    let right = match right {
+      Some(right) => Some(self.fold_expression(right)?),
+      None        => None,
+    };
+
+    self.hide_pattern(&left);
+
+    Ok(Statement::new(StatementNode::Variable(t, left, right), pos))
+  }
+
+  fn fold_assignment(&mut self, left: Expression<'f>, right: Expression<'f>, pos: TokenElement<'f>) -> Result<Statement<'f>, ()> {
+    let right = self.fold_expression(right)?;
+
+    Ok(Statement::new(StatementNode::Assignment(left, right), pos))
+  }
+
+  fn fold_for(&mut self, binding: Expression<'f>, iterable: Expression<'f>, body: Expression<'f>, pos: TokenElement<'f>) -> Result<Statement<'f>, ()> {
+    let iterable = self.fold_expression(iterable)?;
+
+    self.push_scope();
+    self.hide_pattern(&binding);
+
+    let body = self.fold_expression(body);
+
+    self.pop_scope();
+
+    Ok(Statement::new(StatementNode::For(binding, iterable, body?), pos))
+  }
+
+  fn fold_function(&mut self, name: String, generics: Vec<String>, params: Vec<(String, Type)>, return_type: Type, body: Expression<'f>, pos: TokenElement<'f>) -> Result<Statement<'f>, ()> {
+    self.hide(&name);
+    self.push_scope();
+
+    for &(ref param, _) in &params {
+      self.hide(param)
+    }
+
+    let body = self.fold_expression(body);
+
+    self.pop_scope();
+
+    Ok(Statement::new(StatementNode::Function(name, generics, params, return_type, body?), pos))
+  }
+
+  fn fold_struct(&mut self, name: String, generics: Vec<String>, body: Expression<'f>, pos: TokenElement<'f>) -> Result<Statement<'f>, ()> {
+    self.hide(&name);
+
+    Ok(Statement::new(StatementNode::Struct(name, generics, self.fold_expression(body)?), pos))
+  }
+
+  fn fold_block(&mut self, statements: Vec<Statement<'f>>, pos: TokenElement<'f>) -> Result<Expression<'f>, ()> {
+    self.push_scope();
+
+    let statements = self.fold_statements(statements);
+
+    self.pop_scope();
+
+    Ok(Expression::new(ExpressionNode::Block(statements?), pos))
+  }
+
+  fn fold_identifier(&mut self, name: String, pos: TokenElement<'f>) -> Result<Expression<'f>, ()> {
+    if let Some(inlined) = self.lookup(&name).and_then(|constant| Self::inline(constant, pos.clone())) {
+      return Ok(inlined)
+    }
+
+    Ok(Expression::new(ExpressionNode::Identifier(name), pos))
+  }
+
+  fn fold_binary(&mut self, left: Expression<'f>, op: Operator, right: Expression<'f>, pos: TokenElement<'f>) -> Result<Expression<'f>, ()> {
+    let left  = self.fold_expression(left)?;
+    let right = self.fold_expression(right)?;
+
+    let node = match self.evaluate(&left.node, &op, &right.node, &pos)? {
+      Some(node) => node,
+      None       => ExpressionNode::Binary(Rc::new(left), op, Rc::new(right)),
+    };
+
+    Ok(Expression::new(node, pos))
+  }
+
+  // `-5` ends up as a single negative literal
+  fn fold_unary(&mut self, op: Operator, operand: Expression<'f>, pos: TokenElement<'f>) -> Result<Expression<'f>, ()> {
+    let operand = self.fold_expression(operand)?;
+
+    let node = match (&op, &operand.node) {
+      (&Operator::Sub, &ExpressionNode::Int(n))   => ExpressionNode::Int(self.checked(n.checked_neg(), "negation overflows", &pos)?),
+      (&Operator::Sub, &ExpressionNode::Float(n)) => ExpressionNode::Float(-n),
+      _                                           => ExpressionNode::Unary(op, Rc::new(operand)),
+    };
+
+    Ok(Expression::new(node, pos))
+  }
+
+  fn fold_range(&mut self, start: Expression<'f>, end: Expression<'f>, inclusive: bool, step: Option<Expression<'f>>, pos: TokenElement<'f>) -> Result<Expression<'f>, ()> {
+    let start = self.fold_expression(start)?;
+    let end   = self.fold_expression(end)?;
+    let step  = match step {
+      Some(step) => Some(self.fold_expression(step)?),
+      None       => None,
+    };
+
+    if let Some(ref step) = step {
+      match step.node {
+        ExpressionNode::Int(0) | ExpressionNode::Float(0.0) => return Err(
+          response!(
+            Wrong("range step can't be zero"),
+            self.source.file,
+            step.pos
+          )
+        ),
+
+        _ => (),
+      }
+    }
+
+    Ok(Expression::new(ExpressionNode::Range(Rc::new(start), Rc::new(end), inclusive, step.map(Rc::new)), pos))
+  }
+}
@@ -0,0 +1,214 @@
+use super::super::error::Response::{ Wrong, Note, };
+
+use std::collections::HashSet;
+
+use super::*;
+
+// reports reads of variables that aren't assigned on every path leading to them,
+// `hp: int` declares without assigning while `hp: int?` starts out as `nil`
+pub struct DefiniteAssignment<'d> {
+  pub source:  &'d Source,
+  pub aliases: &'d AliasTab,
+
+  scopes:       Vec<Vec<(String, usize)>>, // names in scope and their declaration
+  declarations: Vec<TokenElement<'d>>,
+  assigned:     HashSet<usize>,
+}
+
+impl<'d> DefiniteAssignment<'d> {
+  pub fn new(source: &'d Source, aliases: &'d AliasTab) -> Self {
+    DefiniteAssignment {
+      source,
+      aliases,
+
+      scopes:       vec!(Vec::new()),
+      declarations: Vec::new(),
+      assigned:     HashSet::new(),
+    }
+  }
+
+  fn declare(&mut self, name: &str, pos: &TokenElement<'d>, assigned: bool) {
+    let id = self.declarations.len();
+
+    self.declarations.push(pos.clone());
+    self.scopes.last_mut().unwrap().push((name.to_string(), id));
+
+    if assigned {
+      self.assigned.insert(id);
+    }
+  }
+
+  // every name in a pattern
+  fn declare_pattern(&mut self, pattern: &'d Expression<'d>, assigned: bool) {
+    use self::ExpressionNode::*;
+
+    match pattern.node {
+      Identifier(ref name) => self.declare(name, &pattern.pos, assigned),
+
+      Tuple(ref elements) | List(ref elements) | Fields(ref elements) => for element in elements {
+        self.declare_pattern(element, assigned)
+      },
+
+      _ => (),
+    }
+  }
+
+  fn lookup(&self, name: &str) -> Option<usize> {
+    for scope in self.scopes.iter().rev() {
+      if let Some(&(_, id)) = scope.iter().rev().find(|d| d.0 == name) {
+        return Some(id)
+      }
+    }
+
+    None
+  }
+
+  fn push_scope(&mut self) {
+    self.scopes.push(Vec::new())
+  }
+
+  fn pop_scope(&mut self) {
+    self.scopes.pop();
+  }
+}
+
+impl<'d> Walk<'d> for DefiniteAssignment<'d> {
+  fn walk_variable(&mut self, _statement: &'d Statement<'d>, t: &'d Type, left: &'d Expression<'d>, right: Option<&'d Expression<'d>>) -> Result<(), ()> {
+    if let Some(right) = right {
+      self.walk_expression(right)?
+    }
+
+    let optional = self.aliases.expand(t).map(|t| t.optional().is_some()).unwrap_or(false);
+
+    self.declare_pattern(left, right.is_some() || optional);
+
+    Ok(())
+  }
+
+  fn walk_constant(&mut self, _statement: &'d Statement<'d>, _t: &'d Type, left: &'d Expression<'d>, right: &'d Expression<'d>) -> Result<(), ()> {
+    self.walk_expression(right)?;
+    self.declare_pattern(left, true);
+
+    Ok(())
+  }
+
+  fn walk_assignment(&mut self, _statement: &'d Statement<'d>, left: &'d Expression<'d>, right: &'d Expression<'d>) -> Result<(), ()> {
+    self.walk_expression(right)?;
+
+    match left.node {
+      ExpressionNode::Identifier(ref name) => {
+        if let Some(id) = self.lookup(name) {
+          self.assigned.insert(id);
+        }
+
+        Ok(())
+      },
+
+      // assigning a field reads the value holding it
+      _ => self.walk_expression(left),
+    }
+  }
+
+  fn walk_identifier(&mut self, expression: &'d Expression<'d>, name: &'d str) -> Result<(), ()> {
+    match self.lookup(name) {
+      Some(id) if !self.assigned.contains(&id) => {
+        response!(
+          Wrong(format!("`{}` is used before it's assigned on every path", name)),
+          self.source.file,
+          expression.pos
+        );
+
+        Err(
+          response!(
+            Note(format!("`{}` is declared here without a value", name)),
+            self.declarations[id]
+          )
+        )
+      },
+
+      _ => Ok(()),
+    }
+  }
+
+  fn walk_block(&mut self, _expression: &'d Expression<'d>, statements: &'d [Statement<'d>]) -> Result<(), ()> {
+    self.push_scope();
+    self.walk_statements(statements)?;
+    self.pop_scope();
+
+    Ok(())
+  }
+
+  // a name is assigned after an `if` when both branches assign it, a branch that returns doesn't count
+  fn walk_if(&mut self, _expression: &'d Expression<'d>, condition: &'d Expression<'d>, body: &'d Expression<'d>, otherwise: Option<&'d Expression<'d>>) -> Result<(), ()> {
+    self.walk_expression(condition)?;
+
+    let before = self.assigned.clone();
+
+    self.walk_expression(body)?;
+
+    let after_body = ::std::mem::replace(&mut self.assigned, before);
+
+    if let Some(otherwise) = otherwise {
+      self.walk_expression(otherwise)?
+    }
+
+    let body_returns      = Visitor::always_returns(body);
+    let otherwise_returns = otherwise.map_or(false, Visitor::always_returns);
+
+    self.assigned = match (body_returns, otherwise_returns) {
+      (true, _)      => self.assigned.clone(),
+      (false, true)  => after_body,
+      (false, false) => self.assigned.intersection(&after_body).cloned().collect(),
+    };
+
+    Ok(())
+  }
+
+  // the body may not run at all, so what it assigns doesn't count afterwards
+  fn walk_for(&mut self, _statement: &'d Statement<'d>, binding: &'d Expression<'d>, iterable: &'d Expression<'d>, body: &'d Expression<'d>) -> Result<(), ()> {
+    self.walk_expression(iterable)?;
+
+    let before = self.assigned.clone();
+
+    self.push_scope();
+    self.declare_pattern(binding, true);
+
+    self.walk_expression(body)?;
+
+    self.pop_scope();
+
+    self.assigned = before;
+
+    Ok(())
+  }
+
+  // a function can be called once everything it sees is assigned, so its body assumes that
+  fn walk_function(&mut self, statement: &'d Statement<'d>, name: &'d str, _generics: &'d [String], params: &'d [(String, Type)], _return_type: &'d Type, body: &'d Expression<'d>) -> Result<(), ()> {
+    self.declare(name, &statement.pos, true);
+
+    let before = self.assigned.clone();
+
+    self.assigned = (0 .. self.declarations.len()).collect();
+
+    self.push_scope();
+
+    for &(ref param, _) in params {
+      self.declare(param, &statement.pos, true)
+    }
+
+    self.walk_expression(body)?;
+
+    self.pop_scope();
+
+    self.assigned = before;
+
+    Ok(())
+  }
+
+  // fields aren't variables
+  fn walk_struct(&mut self, statement: &'d Statement<'d>, name: &'d str, _generics: &'d [String], _body: &'d Expression<'d>) -> Result<(), ()> {
+    self.declare(name, &statement.pos, true);
+
+    Ok(())
+  }
+}
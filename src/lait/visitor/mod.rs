@@ -1,10 +1,18 @@
-pub mod symtab;
-pub mod typetab;
+pub mod scopetree;
+pub mod aliastab;
 pub mod visitor;
+pub mod constant;
+pub mod definite;
 
-pub use self::symtab::*;
-pub use self::typetab::*;
+#[cfg(test)]
+mod tests;
+
+pub use self::scopetree::*;
+pub use self::aliastab::*;
 pub use self::visitor::*;
+pub use self::constant::*;
+pub use self::definite::*;
 
 use super::source::Source;
+use super::lexer::TokenElement;
 use super::parser::*;
\ No newline at end of file
@@ -0,0 +1,197 @@
+use super::Type;
+use super::super::lexer::TokenElement;
+
+use std::collections::HashMap;
+
+
+
+pub type ScopeId  = usize;
+pub type SymbolId = usize;
+
+// what a name was declared as
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeclarationKind {
+  Variable,
+  Constant,  // bound with `::`, can't be reassigned and isn't reported as unused since the folder inlines its uses
+  Function,  // functions and struct constructors, never reported as unused
+  Parameter, // part of the function's signature, so neither unused nor shadowing
+  Narrowed,  // the non-nil shadow of a checked optional, introduced by the checker itself
+}
+
+#[derive(Debug, Clone)]
+pub struct Symbol<'s> {
+  pub name:    String,
+  pub t:       Type,
+  pub pos:     TokenElement<'s>,
+  pub kind:    DeclarationKind,
+  pub mutable: bool,
+  pub used:    bool,
+  pub scope:   ScopeId,
+}
+
+#[derive(Debug, Clone)]
+pub struct Scope {
+  pub parent:   Option<ScopeId>,
+  pub children: Vec<ScopeId>,
+  pub depth:    u32,
+  pub names:    HashMap<String, SymbolId>, // the latest declaration of each name
+  pub symbols:  Vec<SymbolId>,             // every declaration, in order
+}
+
+// every scope and symbol of a file, kept around after checking so later passes can look things up by id
+#[derive(Debug, Clone)]
+pub struct ScopeTree<'s> {
+  pub scopes:  Vec<Scope>,
+  pub symbols: Vec<Symbol<'s>>,
+  pub current: ScopeId,
+}
+
+impl<'s> ScopeTree<'s> {
+  pub fn new() -> Self {
+    ScopeTree {
+      scopes:  vec!(Scope::new(None, 0)),
+      symbols: Vec::new(),
+      current: 0,
+    }
+  }
+
+  pub fn push(&mut self) -> ScopeId {
+    let id    = self.scopes.len();
+    let depth = self.depth() + 1;
+
+    self.scopes.push(Scope::new(Some(self.current), depth));
+    self.scopes[self.current].children.push(id);
+
+    self.current = id;
+
+    id
+  }
+
+  // leaves the current scope, giving back the one that was left
+  pub fn pop(&mut self) -> ScopeId {
+    let popped = self.current;
+
+    self.current = self.scopes[popped].parent.expect("[scope tree] popped the global scope");
+
+    popped
+  }
+
+  pub fn depth(&self) -> u32 {
+    self.scopes[self.current].depth
+  }
+
+  pub fn declare(&mut self, name: &str, t: Type, pos: TokenElement<'s>, kind: DeclarationKind, mutable: bool) -> SymbolId {
+    let id = self.symbols.len();
+
+    self.symbols.push(
+      Symbol {
+        name:  name.to_string(),
+        t,
+        pos,
+        kind,
+        mutable,
+        used:  false,
+        scope: self.current,
+      }
+    );
+
+    let scope = &mut self.scopes[self.current];
+
+    scope.names.insert(name.to_string(), id);
+    scope.symbols.push(id);
+
+    id
+  }
+
+  // the declaration a name refers to from the current scope
+  pub fn lookup(&self, name: &str) -> Option<SymbolId> {
+    self.lookup_from(self.current, name)
+  }
+
+  pub fn lookup_from(&self, scope: ScopeId, name: &str) -> Option<SymbolId> {
+    let mut scope = Some(scope);
+
+    while let Some(id) = scope {
+      if let Some(symbol) = self.scopes[id].names.get(name) {
+        return Some(*symbol)
+      }
+
+      scope = self.scopes[id].parent
+    }
+
+    None
+  }
+
+  // the latest declaration of a name within a single scope that came from the source, skipping narrowed shadows
+  pub fn written_in(&self, scope: ScopeId, name: &str) -> Option<SymbolId> {
+    self.scopes[scope].symbols.iter().rev().cloned().find(|&id| {
+      let symbol = &self.symbols[id];
+
+      symbol.name == name && symbol.kind != DeclarationKind::Narrowed
+    })
+  }
+
+  // the declaration a name refers to from the current scope, looking through narrowed shadows
+  pub fn declared(&self, name: &str) -> Option<SymbolId> {
+    self.enclosing().into_iter().filter_map(|scope| self.written_in(scope, name)).next()
+  }
+
+  // undoes the narrowing of a name out to where it's declared, once it's assigned something that may be nil again
+  pub fn drop_narrowed(&mut self, name: &str) {
+    let declared = match self.declared(name) {
+      Some(declared) => self.symbols[declared].scope,
+      None           => return,
+    };
+
+    for scope in self.enclosing() {
+      let narrowed = self.scopes[scope].names.get(name).map_or(false, |&id| self.symbols[id].kind == DeclarationKind::Narrowed);
+
+      if narrowed {
+        match self.written_in(scope, name) {
+          Some(id) => { self.scopes[scope].names.insert(name.to_string(), id); },
+          None     => { self.scopes[scope].names.remove(name); },
+        }
+      }
+
+      if scope == declared {
+        break
+      }
+    }
+  }
+
+  // the scopes from the current one out to the global one
+  pub fn enclosing(&self) -> Vec<ScopeId> {
+    let mut scopes = Vec::new();
+    let mut scope  = Some(self.current);
+
+    while let Some(id) = scope {
+      scopes.push(id);
+
+      scope = self.scopes[id].parent
+    }
+
+    scopes
+  }
+
+  pub fn symbol(&self, id: SymbolId) -> &Symbol<'s> {
+    &self.symbols[id]
+  }
+
+  pub fn symbol_mut(&mut self, id: SymbolId) -> &mut Symbol<'s> {
+    &mut self.symbols[id]
+  }
+}
+
+
+
+impl Scope {
+  pub fn new(parent: Option<ScopeId>, depth: u32) -> Self {
+    Scope {
+      parent,
+      children: Vec::new(),
+      depth,
+      names:    HashMap::new(),
+      symbols:  Vec::new(),
+    }
+  }
+}
@@ -0,0 +1,182 @@
+use super::*;
+
+// whether the source makes it through folding, checking and definite assignment
+fn check(content: &str) -> bool {
+  ::parse("test.lait", content, |source, syntax, ast| {
+    let ast = match ConstantFolder::new(source).fold_statements(ast) {
+      Ok(ast) => ast,
+      _       => return false,
+    };
+
+    let mut visitor = Visitor::new(source, &ast, &syntax.directives());
+
+    visitor.visit().is_ok() && DefiniteAssignment::new(source, &visitor.aliases).walk_statements(&ast).is_ok()
+  }).expect("source doesn't parse")
+}
+
+// how many warnings checking a passing source gives
+fn warnings(content: &str) -> usize {
+  ::parse("test.lait", content, |source, syntax, ast| {
+    let mut visitor = Visitor::new(source, &ast, &syntax.directives());
+
+    visitor.visit().expect("source doesn't check");
+
+    visitor.warnings
+  }).expect("source doesn't parse")
+}
+
+#[test]
+fn float_folding_reports_non_finite_results() {
+  assert!(!check("x := 1.0 / 0.0\n"));
+  assert!(!check("x := 0.0 / 0.0\n"));
+  assert!(!check("x := 1.0 % 0.0\n"));
+  assert!(!check("x := 2.0 ^ 2000\n"));
+  assert!(!check("x := 10.0 ^ 300 * 10.0 ^ 300\n"));
+
+  assert!(check("x := 1.5 * 2\n"));
+  assert!(check("x := 1.0 / 4.0\n"))
+}
+
+#[test]
+fn inferred_int_literals_are_range_checked() {
+  assert!(!check("x := 18446744073709551615\n"));
+  assert!(!check("x := 9223372036854775807 + 1\n"));
+  assert!(!check("x := 5000000000 * 5000000000\n"));
+  assert!(!check("x := [1, 99999999999999999999]\n"));
+
+  assert!(check("x := 9223372036854775807\n"));
+  assert!(check("x := -9223372036854775808\n"));
+  assert!(check("x: u64 = 18446744073709551615\n"))
+}
+
+#[test]
+fn reads_need_an_assignment_on_every_path() {
+  assert!(!check("hp: int\nx := hp\n"));
+  assert!(!check("hp: int\nif true {\n  hp = 1\n}\nx := hp\n"));
+  assert!(!check("hp: int\nfor i in 0 .. 3 {\n  hp = i\n}\nx := hp\n"));
+
+  assert!(check("hp: int\nhp = 1\nx := hp\n"));
+  assert!(check("hp: int\nif true {\n  hp = 1\n} else {\n  hp = 2\n}\nx := hp\n"));
+  assert!(check("fun f(b: bool) -> int {\n  hp: int\n  if b {\n    return 0\n  } else {\n    hp = 1\n  }\n  return hp\n}\n"));
+  assert!(check("hp: int?\nx := hp\n"))
+}
+
+#[test]
+fn unused_shadowed_and_redeclared_variables_warn() {
+  assert_eq!(warnings("fun f() {\n  x := 1\n}\n"), 1);
+  assert_eq!(warnings("x := 1\nfun f() -> int {\n  x := 2\n  return x\n}\ny := x + 1\n"), 1);
+  assert_eq!(warnings("x := 1\nx := \"a\"\ny := x\n"), 1);
+
+  assert_eq!(warnings("fun f() {\n  _x := 1\n}\n"), 0);
+  assert_eq!(warnings("x := 1\nx := 2\ny := x\n"), 0);
+  assert_eq!(warnings("fun f(a: int) -> int {\n  b := a\n  return b\n}\n"), 0)
+}
+
+const FIRST: &str = "fun first<T>(xs: [T]) -> T? {\n  for x in xs {\n    return x\n  }\n  return nil\n}\n";
+const BOX:   &str = "struct Box<T> {\n  v: T\n}\n";
+
+#[test]
+fn generic_type_arguments_are_inferred() {
+  assert!(check(&format!("{}a := first([1, 2, 3])\nb: int? = a\n", FIRST)));
+  assert!(check(&format!("{}b := Box(3)\nn: int = b.v\nc: Box<str> = Box(\"s\")\n", BOX)));
+  assert!(check("fun map<T, U>(xs: [T], f: fun(T) -> U) -> [U] {\n  out: [U] = []\n  for x in xs {\n    out = out ++ [f(x)]\n  }\n  return out\n}\nfun double(n: int) -> int {\n  return n * 2\n}\nd: [int] = map([1, 2], double)\n"));
+
+  assert!(!check(&format!("{}a := first([1, 2, 3])\nb: str? = a\n", FIRST)));
+  assert!(!check(&format!("{}a := first([])\n", FIRST)));
+  assert!(!check("fun pair<T>(a: T, b: T) -> T {\n  return a\n}\nx := pair(1, \"s\")\n"));
+  assert!(!check("fun add<T>(a: T, b: T) -> T {\n  return a + b\n}\n"));
+  assert!(!check(&format!("{}b := Box(3)\nm: str = b.v\n", BOX)))
+}
+
+const DRAWABLE: &str = "trait Drawable {\n  fun draw(self)\n  fun depth(self) -> int\n}\nstruct Sprite {\n  z: int\n}\n";
+
+#[test]
+fn implementations_conform_to_their_trait() {
+  let draw = "  fun draw(self) {\n  }\n";
+
+  assert!(check(&format!("{}implement Drawable for Sprite {{\n{}  fun depth(self) -> int {{\n    return self.z\n  }}\n}}\nfun render(d: Drawable) -> int {{\n  d.draw()\n  return d.depth()\n}}\nn: int = render(Sprite(1))\n", DRAWABLE, draw)));
+  assert!(check(&format!("{}implement Sprite {{\n  fun grow(self) -> int {{\n    return self.z + 1\n  }}\n}}\nn: int = Sprite(1).grow()\n", DRAWABLE)));
+
+  assert!(!check(&format!("{}implement Drawable for Sprite {{\n{}}}\n", DRAWABLE, draw)));
+  assert!(!check(&format!("{}implement Drawable for Sprite {{\n{}  fun depth(self) -> bool {{\n    return true\n  }}\n}}\n", DRAWABLE, draw)));
+  assert!(!check(&format!("{}implement Drawable for Sprite {{\n{}  fun depth(self) -> int {{\n    return 1\n  }}\n  fun size(self) -> int {{\n    return 1\n  }}\n}}\n", DRAWABLE, draw)));
+  assert!(!check(&format!("{}fun render(d: Drawable) -> int {{\n  return d.depth()\n}}\nn := render(Sprite(1))\n", DRAWABLE)));
+  assert!(!check(&format!("{}fun g()\n", DRAWABLE)))
+}
+
+const VEC2: &str = "struct Vec2 {\n  x: int\n  y: int\n}\nimplement Vec2 {\n  fun +(self, other: Vec2) -> Vec2 {\n    return Vec2(self.x + other.x, self.y + other.y)\n  }\n  fun *(self, k: int) -> Vec2 {\n    return Vec2(self.x * k, self.y * k)\n  }\n  fun ==(self, other: Vec2) -> bool {\n    return self.x == other.x\n  }\n}\n";
+
+#[test]
+fn operators_resolve_through_implement_blocks() {
+  assert!(check(&format!("{}a := Vec2(1, 2)\nb: Vec2 = a + a * 2\nsame: bool = a != b\n", VEC2)));
+
+  assert!(!check(&format!("{}a := Vec2(1, 2)\nc: int = a + a\n", VEC2)));
+  assert!(!check(&format!("{}a := Vec2(1, 2)\nc := a - a\n", VEC2)));
+  assert!(!check(&format!("{}a := Vec2(1, 2)\nc := a * a\n", VEC2)));
+  assert!(!check("struct V {\n  x: int\n}\nimplement V {\n  fun <(self, o: V) -> int {\n    return 1\n  }\n}\n"));
+  assert!(!check("struct V {\n  x: int\n}\nimplement V {\n  fun +(self) -> V {\n    return self\n  }\n}\n"))
+}
+
+#[test]
+fn functions_return_on_every_path() {
+  assert!(check("fun sign(n: int) -> int {\n  if n < 0 {\n    return -1\n  } else {\n    return 1\n  }\n}\n"));
+  assert!(check("fun first(xs: [int]) -> int? {\n  for x in xs {\n    return x\n  }\n  return nil\n}\n"));
+  assert!(check("fun f(n: int) {\n  if n > 0 {\n    return\n  }\n}\n"));
+
+  assert!(!check("fun half(n: int) -> int {\n  if n > 0 {\n    return n / 2\n  }\n}\n"));
+  assert!(!check("fun first(xs: [int]) -> int {\n  for x in xs {\n    return x\n  }\n}\n"))
+}
+
+#[test]
+fn code_after_a_return_warns() {
+  assert_eq!(warnings("fun noisy(n: int) -> int {\n  return n\n  n = n + 1\n  n = n * 2\n}\n"), 1);
+  assert_eq!(warnings("fun f(n: int) {\n  if n > 0 {\n    return\n  } else {\n    return\n  }\n  n = 1\n}\n"), 1);
+
+  assert_eq!(warnings("fun f(n: int) -> int {\n  if n > 0 {\n    return 1\n  }\n  return n\n}\n"), 0)
+}
+
+const ENEMY: &str = "struct Enemy {\n  hp: int\n}\n";
+
+#[test]
+fn narrowed_names_can_be_assigned_their_declared_type() {
+  assert!(check(&format!("{}target: Enemy? = Enemy(3)\nif target != nil {{\n  target = nil\n}}\n", ENEMY)));
+  assert!(check(&format!("{}target: Enemy? = Enemy(3)\nother: Enemy? = nil\nif target != nil {{\n  target = other\n}}\n", ENEMY)));
+  assert!(check(&format!("{}fun f(t: Enemy?) {{\n  if t == nil {{\n    return\n  }}\n  t = nil\n}}\n", ENEMY)))
+}
+
+#[test]
+fn assigning_nil_undoes_narrowing() {
+  assert!(!check(&format!("{}fun f(t: Enemy?) -> int {{\n  if t == nil {{\n    return 0\n  }}\n  t = nil\n  return t.hp\n}}\n", ENEMY)));
+  assert!(check(&format!("{}fun f(t: Enemy?) -> int {{\n  t = Enemy(1)\n  return t.hp\n}}\n", ENEMY)))
+}
+
+#[test]
+fn constants_stay_constant() {
+  assert!(!check("k :: 1\nk = 2\n"));
+  assert!(!check("k :: 1\nk := 5\nk = 6\n"));
+  assert!(!check("k :: 1\nk :: 2\n"));
+
+  assert!(check("k :: 1\nfun f() {\n  k := 2\n  k = 3\n}\n"));
+  assert!(check("k :: 1\nn: u8 = k\n"))
+}
+
+#[test]
+fn any_values_record_runtime_checks() {
+  let content = "x: any = 3\ny: int = x\nz := x as float\nif x {\n  w: any = y\n}\n";
+
+  let checks = ::parse("test.lait", content, |source, syntax, ast| {
+    let mut visitor = Visitor::new(source, &ast, &syntax.directives());
+
+    visitor.visit().expect("source doesn't check");
+
+    checks_to_json(&visitor.checks)
+  }).expect("source doesn't parse");
+
+  // `w: any = y` goes the other way, anything fits into `any` without a check
+  assert_eq!(
+    checks,
+    "[{\"type\":\"int\",\"span\":{\"line\":2,\"start\":10,\"end_line\":2,\"end\":10}},\
+{\"type\":\"float\",\"span\":{\"line\":3,\"start\":6,\"end_line\":3,\"end\":6}},\
+{\"type\":\"bool\",\"span\":{\"line\":4,\"start\":4,\"end_line\":4,\"end\":4}}]"
+  )
+}
@@ -1,7 +1,8 @@
-use super::super::error::Response::Wrong;
+use super::super::error::Response::{ Wrong, Weird, Note, };
 
 use std::rc::Rc;
 use std::fmt;
+use std::collections::{ HashMap, HashSet, };
 
 use super::*;
 
@@ -10,26 +11,320 @@ use super::*;
 pub enum Type {
   Int,
   Float,
+  I8, I16, I32, I64,
+  U8, U16, U32, U64,
+  F32, F64,
   Char,
   String,
   Bool,
   Nil,
+  Any, // checked at runtime instead, wherever it meets another type
   Id(String),
+  Range(Rc<Type>),
+  Tuple(Vec<Type>),
+  List(Rc<Type>),
+  Fun(Vec<Type>, Rc<Type>), // params, return type
+  Optional(Rc<Type>),
+  Param(String),                  // a type parameter, opaque inside the declaration it belongs to
+  Instance(String, Vec<Type>),    // a generic struct with its type arguments, `Pool<int>`
+  Generic(Vec<String>, Rc<Type>), // a generic function or constructor, instantiated wherever it's called
 }
 
 impl Type {
+  // literals that fit where their own type wouldn't
   pub fn check_expression(&self, other: &ExpressionNode) -> bool {
     use self::Type::*;
 
     match *other {
-      ExpressionNode::Int(_) => match *self {
-        Int | Float => true,
-        _           => false,
+      // integer literals fit any number type that can hold them
+      ExpressionNode::Int(n) => match self.integer_range() {
+        Some((min, max)) => n >= min && n <= max,
+        None             => self.float().is_some(),
+      },
+
+      ExpressionNode::Float(n) => match self.float() {
+        Some(32) => n.abs() <= f32::MAX as f64 || !n.is_finite(),
+        Some(_)  => true,
+        None     => false,
+      },
+
+      // `[]` fits any list
+      ExpressionNode::List(ref elements) if elements.is_empty() => match *self {
+        List(_) => true,
+        _       => false,
       },
 
       _ => false
     }
   }
+
+  // the implicit conversion policy: a value is used as-is where its own type is
+  // expected, an integer widens to any integer that holds all of its values and
+  // to any float, and a float widens to a float at least as wide. nothing narrows
+  // implicitly, that takes an explicit `as`. declarations, assignments, arguments,
+  // returns and operands all go through here
+  pub fn widens_to(&self, target: &Type) -> bool {
+    if self == target || *target == Type::Any {
+      return true
+    }
+
+    // `nil` and anything that fits `T` fit `T?`
+    match (self, target) {
+      (&Type::Nil, &Type::Optional(_))                  => return true,
+      (&Type::Optional(ref a), &Type::Optional(ref b)) => return a.widens_to(b),
+      (a, &Type::Optional(ref b))                       => return a.widens_to(b),
+      _                                                 => (),
+    }
+
+    match (self.integer(), target.integer()) {
+      (Some((signed, from)), Some((target_signed, to))) => match (signed, target_signed) {
+        (false, true) => from < to,
+        (true, false) => false,
+        _             => from <= to,
+      },
+
+      (Some(_), None) => target.float().is_some(),
+
+      _ => match (self.float(), target.float()) {
+        (Some(from), Some(to)) => from <= to,
+        _                      => false,
+      },
+    }
+  }
+
+  // signedness and width, `int` is a signed 64 bit integer
+  pub fn integer(&self) -> Option<(bool, u32)> {
+    use self::Type::*;
+
+    let integer = match *self {
+      Int | I64 => (true, 64),
+      I8        => (true, 8),
+      I16       => (true, 16),
+      I32       => (true, 32),
+      U8        => (false, 8),
+      U16       => (false, 16),
+      U32       => (false, 32),
+      U64       => (false, 64),
+      _         => return None,
+    };
+
+    Some(integer)
+  }
+
+  // width, `float` is a 64 bit float
+  pub fn float(&self) -> Option<u32> {
+    use self::Type::*;
+
+    match *self {
+      Float | F64 => Some(64),
+      F32         => Some(32),
+      _           => None,
+    }
+  }
+
+  // smallest and largest value of an integer type
+  pub fn integer_range(&self) -> Option<(i128, i128)> {
+    self.integer().map(|(signed, bits)| if signed {
+      (-(1 << (bits - 1)), (1 << (bits - 1)) - 1)
+    } else {
+      (0, (1 << bits) - 1)
+    })
+  }
+
+  // the type both sides can be seen as, e.g. the branches of an `if`, `T` and `nil` make a `T?`
+  pub fn unify(&self, other: &Type) -> Option<Type> {
+    if *self == Type::Any || *other == Type::Any {
+      Some(Type::Any)
+    } else if self.widens_to(other) {
+      Some(other.clone())
+    } else if other.widens_to(self) {
+      Some(self.clone())
+    } else {
+      match (self, other) {
+        (&Type::Nil, t) | (t, &Type::Nil) => Some(Type::Optional(Rc::new(t.clone()))),
+        _                                 => None,
+      }
+    }
+  }
+
+  // whether a value of this type could be what's expected, an `any` anywhere in it stands for whatever
+  // is found in the same place. values that only fit this way get a runtime check
+  pub fn consistent_with(&self, expected: &Type) -> bool {
+    use self::Type::*;
+
+    let all = |a: &[Type], b: &[Type]| a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.consistent_with(b));
+
+    match (self, expected) {
+      (&Any, _) | (_, &Any) => true,
+
+      (&Range(ref a), &Range(ref b)) | (&List(ref a), &List(ref b)) | (&Optional(ref a), &Optional(ref b)) => a.consistent_with(b),
+
+      (a, &Optional(ref b))                                        => a.consistent_with(b),
+      (&Tuple(ref a), &Tuple(ref b))                               => all(a, b),
+      (&Fun(ref a, ref a_return), &Fun(ref b, ref b_return))       => all(a, b) && a_return.consistent_with(b_return),
+      (&Instance(ref a_name, ref a), &Instance(ref b_name, ref b)) => a_name == b_name && all(a, b),
+
+      (a, b) => a.widens_to(b),
+    }
+  }
+
+  pub fn has_any(&self) -> bool {
+    use self::Type::*;
+
+    match *self {
+      Any                                          => true,
+      Range(ref t) | List(ref t) | Optional(ref t) => t.has_any(),
+      Tuple(ref types) | Instance(_, ref types)    => types.iter().any(|t| t.has_any()),
+      Fun(ref params, ref return_type)             => params.iter().any(|t| t.has_any()) || return_type.has_any(),
+      Generic(_, ref t)                            => t.has_any(),
+      _                                            => false,
+    }
+  }
+
+  pub fn optional(&self) -> Option<&Type> {
+    match *self {
+      Type::Optional(ref t) => Some(t),
+      _                     => None,
+    }
+  }
+
+  pub fn is_numeric(&self) -> bool {
+    self.integer().is_some() || self.float().is_some()
+  }
+
+  // what `as` allows on top of widening: any number to any number, `char` to an integer and back, `bool` to an integer
+  pub fn casts_to(&self, target: &Type) -> bool {
+    use self::Type::*;
+
+    match (self, target) {
+      (a, b) if a.widens_to(b)                   => true,
+      (&Any, _)                                  => true,
+      (a, b) if a.is_numeric() && b.is_numeric() => true,
+      (&Char, b) | (&Bool, b)                    => b.integer().is_some(),
+      (a, &Char)                                 => a.integer().is_some(),
+      _                                          => false,
+    }
+  }
+
+  // the type with `f` applied to every part of it, from the outside in, `None` leaves a part as it is
+  pub fn map<F: Fn(&Type) -> Option<Type>>(&self, f: &F) -> Type {
+    use self::Type::*;
+
+    if let Some(t) = f(self) {
+      return t
+    }
+
+    let map_all = |types: &[Type]| types.iter().map(|t| t.map(f)).collect();
+
+    match *self {
+      Range(ref t)                     => Range(Rc::new(t.map(f))),
+      List(ref t)                      => List(Rc::new(t.map(f))),
+      Optional(ref t)                  => Optional(Rc::new(t.map(f))),
+      Tuple(ref types)                 => Tuple(map_all(types)),
+      Fun(ref params, ref return_type) => Fun(map_all(params), Rc::new(return_type.map(f))),
+      Instance(ref name, ref types)    => Instance(name.clone(), map_all(types)),
+      Generic(ref names, ref t)        => Generic(names.clone(), Rc::new(t.map(f))),
+      ref t                            => t.clone(),
+    }
+  }
+
+  // the type with its type parameters replaced by their arguments
+  pub fn substitute(&self, arguments: &HashMap<String, Type>) -> Type {
+    self.map(&|t| match *t {
+      Type::Param(ref name) => arguments.get(name).cloned(),
+      _                     => None,
+    })
+  }
+
+  // matches the type of a parameter against the type of its argument, picking up what its
+  // type parameters stand for. an `Err` holds a parameter that was given two types that don't unify
+  pub fn infer(&self, found: &Type, arguments: &mut HashMap<String, Type>) -> Result<(), (String, Type, Type)> {
+    use self::Type::*;
+
+    match (self, found) {
+      // `nil` and `[]` say nothing about what they hold
+      (_, &Nil) => Ok(()),
+
+      (&Param(ref name), found) => {
+        let inferred = match arguments.get(name) {
+          Some(t) => match t.unify(found) {
+            Some(t) => t,
+            None    => return Err((name.clone(), t.clone(), found.clone())),
+          },
+
+          None => found.clone(),
+        };
+
+        arguments.insert(name.clone(), inferred);
+
+        Ok(())
+      },
+
+      (&Range(ref a), &Range(ref b)) | (&List(ref a), &List(ref b)) | (&Optional(ref a), &Optional(ref b)) => a.infer(b, arguments),
+
+      (&Optional(ref a), found) => a.infer(found, arguments),
+
+      (&Tuple(ref a), &Tuple(ref b)) if a.len() == b.len() => {
+        for (a, b) in a.iter().zip(b) {
+          a.infer(b, arguments)?
+        }
+
+        Ok(())
+      },
+
+      (&Instance(ref a_name, ref a), &Instance(ref b_name, ref b)) if a_name == b_name && a.len() == b.len() => {
+        for (a, b) in a.iter().zip(b) {
+          a.infer(b, arguments)?
+        }
+
+        Ok(())
+      },
+
+      (&Fun(ref a, ref a_return), &Fun(ref b, ref b_return)) if a.len() == b.len() => {
+        for (a, b) in a.iter().zip(b) {
+          a.infer(b, arguments)?
+        }
+
+        a_return.infer(b_return, arguments)
+      },
+
+      // anything else is left to the type check on the argument
+      _ => Ok(()),
+    }
+  }
+
+  // the type of `self op other`, if the operator applies at all
+  pub fn binary(&self, op: &Operator, other: &Type) -> Option<Type> {
+    use self::Type::*;
+    use self::Operator::*;
+
+    if *self == Any || *other == Any {
+      return Some(if op.is_comparison() { Bool } else { Any })
+    }
+
+    let numbers = if self.is_numeric() && other.is_numeric() {
+      self.unify(other)
+    } else {
+      None
+    };
+
+    match *op {
+      Add | Sub | Mul | Div | Mod | Pow => numbers,
+
+      Concat => match (self, other) {
+        (&String, &String)                 => Some(String),
+        (&List(ref a), &List(ref b)) if a == b => Some(self.clone()),
+        _                                  => None,
+      },
+
+      Lt | Gt | LtEq | GtEq => match (self, other) {
+        (&String, &String) | (&Char, &Char) => Some(Bool),
+        _                                   => numbers.map(|_| Bool),
+      },
+
+      Eq | NEq => self.unify(other).map(|_| Bool),
+    }
+  }
 }
 
 impl fmt::Display for Type {
@@ -37,13 +332,56 @@ impl fmt::Display for Type {
     use self::Type::*;
 
     match *self {
-      Int        => write!(f, "int"),
-      Float      => write!(f, "float"),
-      Char       => write!(f, "char"),
-      String     => write!(f, "str"),
-      Bool       => write!(f, "bool"),
-      Nil        => write!(f, "nil"),
-      Id(ref id) => write!(f, "{}", id),
+      Int          => write!(f, "int"),
+      Float        => write!(f, "float"),
+      Char         => write!(f, "char"),
+      String       => write!(f, "str"),
+      Bool         => write!(f, "bool"),
+      Nil          => write!(f, "nil"),
+      Any          => write!(f, "any"),
+      I8           => write!(f, "i8"),
+      I16          => write!(f, "i16"),
+      I32          => write!(f, "i32"),
+      I64          => write!(f, "i64"),
+      U8           => write!(f, "u8"),
+      U16          => write!(f, "u16"),
+      U32          => write!(f, "u32"),
+      U64          => write!(f, "u64"),
+      F32          => write!(f, "f32"),
+      F64          => write!(f, "f64"),
+      Id(ref id)   => write!(f, "{}", id),
+      Param(ref p) => write!(f, "{}", p),
+      Range(ref t) => write!(f, "range<{}>", t),
+      List(ref t)  => write!(f, "[{}]", t),
+
+      Optional(ref t) => match **t {
+        Fun(..) | Generic(..) => write!(f, "({})?", t),
+        _                     => write!(f, "{}?", t),
+      },
+
+      Instance(ref name, ref types) => write!(f, "{}<{}>", name, types.iter().map(|t| t.to_string()).collect::<Vec<_>>().join(", ")),
+
+      // `fun<T>([T]) -> T?`
+      Generic(ref names, ref t) => {
+        let t = t.to_string();
+
+        match t.find('(') {
+          Some(paren) => write!(f, "{}<{}>{}", &t[.. paren], names.join(", "), &t[paren ..]),
+          None        => write!(f, "<{}> {}", names.join(", "), t),
+        }
+      },
+
+      Tuple(ref types) => write!(f, "({})", types.iter().map(|t| t.to_string()).collect::<Vec<_>>().join(", ")),
+
+      Fun(ref params, ref return_type) => {
+        write!(f, "fun({})", params.iter().map(|t| t.to_string()).collect::<Vec<_>>().join(", "))?;
+
+        if **return_type != Nil {
+          write!(f, " -> {}", return_type)?
+        }
+
+        Ok(())
+      },
     }
   }
 }
@@ -51,34 +389,62 @@ impl fmt::Display for Type {
 
 
 pub struct Visitor<'v> {
-  pub tabs:       Vec<(SymTab, TypeTab)>,
-  pub tab_frames: Vec<(SymTab, TypeTab)>,
+  pub scopes: ScopeTree<'v>,
 
   pub source: &'v Source,
   pub ast:    &'v Vec<Statement<'v>>,
 
-  pub depth: u32,
+  pub return_types: Vec<Type>,   // one per function being visited
+  pub generics:     Vec<String>, // type parameters of the declarations being visited
+  pub structs:      HashMap<String, (Vec<String>, Vec<(String, Type)>)>, // type params, fields
+  pub aliases:      AliasTab,
+
+  pub traits:          HashMap<String, Vec<(String, Type)>>, // method signatures, `self` being `Self`
+  pub methods:         HashMap<String, Vec<(String, Type)>>, // by the type they're implemented for
+  pub implementations: HashSet<(String, String)>,            // type, trait
+  pub self_type:       Option<Type>,                         // what `self` is inside a trait or `implement` block
+
+  pub strict: bool,                          // `-- lait: strict`, no implicit `any`
+  pub checks: Vec<(TokenElement<'v>, Type)>, // where an `any` value has to be checked against a type at runtime
+
+  pub types: HashMap<*const Expression<'v>, Type>, // what each expression checked out as, for the passes after this one
+
+  pub warnings: usize, // `weird`s reported, they don't fail the check
 }
 
 impl<'v> Visitor<'v> {
-  pub fn new(source: &'v Source, ast: &'v Vec<Statement<'v>>) -> Self {
+  pub fn new(source: &'v Source, ast: &'v Vec<Statement<'v>>, directives: &[Directive]) -> Self {
     Visitor {
-      tabs:       vec!((SymTab::global(), TypeTab::global())),
-      tab_frames: Vec::new(),
+      scopes: ScopeTree::new(),
 
       source,
       ast,
-      depth:   0,
+      return_types: Vec::new(),
+      generics:     Vec::new(),
+      structs:      HashMap::new(),
+      aliases:      AliasTab::new(),
+
+      traits:          HashMap::new(),
+      methods:         HashMap::new(),
+      implementations: HashSet::new(),
+      self_type:       None,
+
+      strict: directives.iter().any(|d| d.text == "strict"),
+      checks: Vec::new(),
+
+      types: HashMap::new(),
+
+      warnings: 0,
     }
   }
 
   pub fn visit(&mut self) -> Result<(), ()> {
+    self.collect_aliases()?;
+
     for statement in self.ast {
       self.visit_statement(&statement)?
     }
 
-    self.tab_frames.push(self.tabs.last().unwrap().clone());
-
     Ok(())
   }
 
@@ -88,152 +454,1681 @@ impl<'v> Visitor<'v> {
     match statement.node {
       Expression(ref expression) => self.visit_expression(expression),
 
-      Variable(_, ref left, _) => match left.node {
-        ExpressionNode::Identifier(_) => {
-          self.visit_variable(&statement.node)
-        },
+      Variable(ref t, ref left, ref right) => self.visit_variable(t, left, right.as_ref(), DeclarationKind::Variable),
+      Constant(ref t, ref left, ref right) => self.visit_variable(t, left, Some(right), DeclarationKind::Constant),
 
-        _ => Ok(())
-      },
-    }
-  }
+      Assignment(ref left, ref right) => self.visit_assignment(left, right),
 
-  fn visit_expression(&mut self, expression: &'v Expression<'v>) -> Result<(), ()> {
-    use self::ExpressionNode::*;
+      For(ref binding, ref iterable, ref body) => self.visit_for(binding, iterable, body),
 
-    match expression.node {
-      Identifier(ref name) => if self.current_tab().0.get_name(name).is_none() {
+      Function(ref name, ref generics, ref params, ref return_type, ref body) => self.visit_function(name, generics, params, return_type, body, statement),
+      Return(ref value)                                                        => self.visit_return(value.as_ref(), statement),
+      Struct(ref name, ref generics, ref body)                                 => self.visit_struct(name, generics, body, statement),
+      Trait(ref name, ref body)                                                => self.visit_trait(name, body, statement),
+      Implement(ref trait_name, ref t, ref body)                               => self.visit_implement(trait_name.as_ref(), t, body, statement),
+
+      Signature(ref name, ..) => Err(
+        response!(
+          Wrong(format!("function `{}` has no body, only methods in a trait go without one", name)),
+          self.source.file,
+          statement.pos
+        )
+      ),
+
+      // top level aliases are taken care of by `collect_aliases`
+      Alias(..) => if self.scopes.depth() > 0 {
         Err(
           response!(
-            Wrong(format!("no such value `{}` in this scope", name)),
+            Wrong("type aliases can only be declared at the top level"),
             self.source.file,
-            expression.pos
+            statement.pos
           )
         )
       } else {
         Ok(())
       },
+    }
+  }
 
-      _ => Ok(()),
+  // expressions in statement position, their value is thrown away
+  fn visit_expression(&mut self, expression: &'v Expression<'v>) -> Result<(), ()> {
+    use self::ExpressionNode::*;
+
+    match expression.node {
+      Block(_) => self.type_block(expression, false)?,
+      If(..)   => self.type_if(expression, false)?,
+      _        => self.type_expression(expression)?,
+    };
+
+    Ok(())
+  }
+
+
+
+  // aliases are known ahead of the statements, so they can be used before they're declared
+  fn collect_aliases(&mut self) -> Result<(), ()> {
+    for statement in self.ast {
+      if let StatementNode::Alias(ref name, ref t) = statement.node {
+        if !self.aliases.add_alias(name, t.clone()) {
+          return Err(
+            response!(
+              Wrong(format!("type alias `{}` is already defined", name)),
+              self.source.file,
+              statement.pos
+            )
+          )
+        }
+      }
+    }
+
+    for statement in self.ast {
+      if let StatementNode::Alias(ref name, _) = statement.node {
+        if let Err(cycle) = self.aliases.expand(&Type::Id(name.clone())) {
+          return Err(
+            response!(
+              Wrong(format!("type alias `{}` is recursive, `{} = {}`", name, cycle.join(" = "), cycle[0])),
+              self.source.file,
+              statement.pos
+            )
+          )
+        }
+      }
     }
+
+    Ok(())
   }
 
+  // the type with its aliases expanded and type parameters in scope told apart from other names,
+  // cycles were ruled out by `collect_aliases`
+  fn resolve(&self, t: &Type) -> Type {
+    let t = self.aliases.expand(t).unwrap_or_else(|_| t.clone());
 
+    t.map(&|t| match *t {
+      Type::Id(ref name) if self.generics.contains(name) => Some(Type::Param(name.clone())),
+      Type::Id(ref name) if name == "Self"               => self.self_type.clone(),
+      _                                                 => None,
+    })
+  }
 
-  fn visit_variable(&mut self, variable: &'v StatementNode) -> Result<(), ()> {
-    use self::ExpressionNode::Identifier;
+  // the fields of a struct type, with the type arguments of a generic one filled in
+  fn fields_of(&self, t: &Type) -> Option<Vec<(String, Type)>> {
+    let (name, arguments) = match *t {
+      Type::Id(ref name)                   => (name, &[][..]),
+      Type::Instance(ref name, ref types) => (name, &types[..]),
+      _                                    => return None,
+    };
 
-    if let &StatementNode::Variable(ref variable_type, ref left, ref right) = variable {
-      match left.node {
-        Identifier(ref name) => {
-          let index = if let Some((index, _)) = self.current_tab().0.get_name(name) {
-            index
-          } else {
-            self.current_tab().0.add_name(name)
-          };
+    match self.structs.get(name) {
+      Some(&(ref generics, ref fields)) if generics.len() == arguments.len() => {
+        let arguments = generics.iter().cloned().zip(arguments.iter().cloned()).collect();
 
-          self.current_tab().1.grow();
+        Some(fields.iter().map(|&(ref field, ref t)| (field.clone(), t.substitute(&arguments))).collect())
+      },
 
-          if let &Some(ref right) = right {
-            self.visit_expression(&right)?;
+      _ => None,
+    }
+  }
 
-            let right_type = self.type_expression(&right)?;
+  // a generic declaration's type, `fun<T>(..)`, or the type as it is when there's nothing generic about it
+  fn generalize(generics: &[String], t: Type) -> Type {
+    if generics.is_empty() {
+      t
+    } else {
+      Type::Generic(generics.to_vec(), Rc::new(t))
+    }
+  }
 
-            if *variable_type != Type::Nil {
-              if !variable_type.check_expression(&Parser::fold_expression(right)?.node) && *variable_type != right_type {
-                return Err(
-                  response!(
-                    Wrong(format!("mismatched types, expected type `{}` got `{}`", variable_type, right_type)),
-                    self.source.file,
-                    right.pos
-                  )
-                )
-              } else {
-                let depth  = self.depth;
-                
-                self.current_tab().1.set_type(index, 0, (variable_type.to_owned(), depth))?;
-              }
-
-            } else {
-              let depth  = self.depth;
-              
-              self.current_tab().1.set_type(index, 0, (right_type, depth))?;
-            }
 
-          } else {
-            let depth = self.depth;
 
-            self.current_tab().1.set_type(index, 0, (variable_type.to_owned(), depth))?;
-          }
+  // `name := value` and `name :: value` alike, `kind` tells whether the names can be assigned later
+  fn visit_variable(&mut self, variable_type: &Type, left: &'v Expression<'v>, right: Option<&'v Expression<'v>>, kind: DeclarationKind) -> Result<(), ()> {
+    let variable_type = &self.resolve(variable_type);
 
-          Ok(())
-        },
+    let t = if let Some(right) = right {
+      let right_type = self.type_for(right, variable_type)?;
 
-        _ => return Err(
+      if *variable_type != Type::Nil {
+        self.check_type(variable_type, &right_type, right)?;
+
+        variable_type.to_owned()
+      } else if right_type == Type::Nil {
+        return Err(
           response!(
-            Wrong("unexpected variable declaration"),
+            Wrong("can't infer a type from `nil`, declare it as optional, e.g. `name: int? = nil`"),
             self.source.file,
-            left.pos
+            right.pos
           )
         )
+      } else {
+        self.check_inferred(right)?;
+
+        right_type
       }
     } else {
-      unreachable!()
-    }
-  }
-
+      variable_type.to_owned()
+    };
 
+    self.bind(left, t, kind)
+  }
 
-  pub fn type_expression(&mut self, expression: &'v Expression<'v>) -> Result<Type, ()> {
-    use self::ExpressionNode::*;
+  // integer literals without a type to fit end up as `int`, folded ones included
+  fn check_inferred(&self, value: &'v Expression<'v>) -> Result<(), ()> {
+    match value.node {
+      ExpressionNode::Int(n) if !Type::Int.check_expression(&value.node) => {
+        let message = if Type::U64.check_expression(&value.node) {
+          format!("literal out of range for `int`, declare it as `u64` to hold `{}`", n)
+        } else {
+          format!("literal out of range for `int`, no integer type holds `{}`", n)
+        };
 
-    let t = match expression.node {
-      Identifier(ref name) => if let Some((index, env_index)) = self.current_tab().0.get_name(name) {
-        self.current_tab().1.get_type(index, env_index)?
-      } else {
-        return Err(
+        Err(
           response!(
-            Wrong(format!("no such value `{}` in this scope", name)),
+            Wrong(message),
             self.source.file,
-            expression.pos
+            value.pos
           )
         )
       },
 
-      String(_) => Type::String,
-      Char(_)   => Type::Char,
-      Bool(_)   => Type::Bool,
-      Int(_)    => Type::Int,
-      Float(_)  => Type::Float,
+      ExpressionNode::Tuple(ref elements) | ExpressionNode::List(ref elements) => {
+        for element in elements {
+          self.check_inferred(element)?
+        }
 
-      _ => Type::Nil,
-    };
+        Ok(())
+      },
 
-    Ok(t)
+      _ => Ok(()),
+    }
   }
 
+  fn visit_assignment(&mut self, left: &'v Expression<'v>, right: &'v Expression<'v>) -> Result<(), ()> {
+    match left.node {
+      // narrowing only holds for reading, a write goes against the declared type and may undo it
+      ExpressionNode::Identifier(ref name) => {
+        self.check_mutable(left)?;
 
+        let left_type = match self.scopes.declared(name) {
+          Some(id) => {
+            let symbol = self.scopes.symbol_mut(id);
 
-  pub fn current_tab(&mut self) -> &mut (SymTab, TypeTab) {
-    let len = self.tabs.len() - 1;
+            symbol.used = true;
+            symbol.t.clone()
+          },
 
-    &mut self.tabs[len]
-  }
+          None => self.type_expression(left)?,
+        };
 
+        let right_type = self.type_for(right, &left_type)?;
 
+        self.check_type(&left_type, &right_type, right)?;
 
-  pub fn push_scope(&mut self) {
-    let local_symtab  = SymTab::new(Rc::new(self.current_tab().0.clone()), &[]);
-    let local_typetab = TypeTab::new(Rc::new(self.current_tab().1.clone()), &[]);
+        self.scopes.drop_narrowed(name);
+
+        if let Some(t) = left_type.optional() {
+          if right_type.optional().is_none() && right_type != Type::Nil && right_type != Type::Any {
+            self.declare(name, t.clone(), &left.pos, DeclarationKind::Narrowed)?
+          }
+        }
+
+        Ok(())
+      },
+
+      ExpressionNode::Field(..) => {
+        self.check_mutable(left)?;
+
+        let left_type  = self.type_expression(left)?;
+        let right_type = self.type_for(right, &left_type)?;
 
-    self.tabs.push((local_symtab.clone(), local_typetab.clone()));
+        self.check_type(&left_type, &right_type, right)
+      },
 
-    self.depth += 1
+      _ => Err(
+        response!(
+          Wrong("can't assign to this expression"),
+          self.source.file,
+          left.pos
+        )
+      )
+    }
   }
 
-  pub fn pop_scope(&mut self) {
-    self.tab_frames.push(self.tabs.pop().unwrap());
+  // assignments go through the name at the root of `a.b.c`, which has to be declared with `:`
+  fn check_mutable(&mut self, left: &'v Expression<'v>) -> Result<(), ()> {
+    let mut root = left;
+
+    while let ExpressionNode::Field(ref value, _) = root.node {
+      root = value
+    }
+
+    if let ExpressionNode::Identifier(ref name) = root.node {
+      if let Some(id) = self.scopes.declared(name) {
+        let symbol = self.scopes.symbol(id).clone();
+
+        if !symbol.mutable {
+          let (what, note) = match symbol.kind {
+            DeclarationKind::Function => ("function", format!("`{}` is declared here", name)),
+            _                         => ("constant", format!("`{}` is declared here with `::`, use `:=` to make it assignable", name)),
+          };
+
+          let message = match left.node {
+            ExpressionNode::Identifier(_) => format!("can't assign to {} `{}`", what, name),
+            _                             => format!("can't assign to a field of {} `{}`", what, name),
+          };
+
+          response!(
+            Wrong(message),
+            self.source.file,
+            left.pos
+          );
+
+          return Err(
+            response!(
+              Note(note),
+              symbol.pos
+            )
+          )
+        }
+      }
+    }
 
-    self.depth -= 1
+    Ok(())
   }
-}
\ No newline at end of file
+
+  // declares the names on the left of a declaration, taking tuples apart
+  fn bind(&mut self, left: &'v Expression<'v>, t: Type, kind: DeclarationKind) -> Result<(), ()> {
+    use self::ExpressionNode::*;
+
+    // whatever shape the pattern has, the parts of an `any` are `any` too
+    if t == Type::Any {
+      match left.node {
+        Tuple(ref parts) | List(ref parts) | Fields(ref parts) => {
+          for part in parts {
+            self.bind(part, Type::Any, kind)?
+          }
+
+          return Ok(())
+        },
+
+        _ => (),
+      }
+    }
+
+    match left.node {
+      Identifier(ref name) => self.declare(name, t, &left.pos, kind),
+
+      Tuple(ref names) => match t {
+        Type::Tuple(ref types) if types.len() == names.len() => {
+          for (name, t) in names.iter().zip(types) {
+            self.bind(name, t.clone(), kind)?
+          }
+
+          Ok(())
+        },
+
+        Type::Tuple(ref types) => Err(
+          response!(
+            Wrong(format!("expected {} values, found a tuple of {}", names.len(), types.len())),
+            self.source.file,
+            left.pos
+          )
+        ),
+
+        ref t => Err(
+          response!(
+            Wrong(format!("can't take apart value of type `{}` into {} names", t, names.len())),
+            self.source.file,
+            left.pos
+          )
+        ),
+      },
+
+      List(ref elements) => match t {
+        Type::List(ref element) => {
+          for pattern in elements {
+            self.bind(pattern, (**element).clone(), kind)?
+          }
+
+          Ok(())
+        },
+
+        ref t => Err(
+          response!(
+            Wrong(format!("can't take apart value of type `{}` as a list", t)),
+            self.source.file,
+            left.pos
+          )
+        ),
+      },
+
+      Fields(ref names) => {
+        let fields = match self.fields_of(&t) {
+          Some(fields) => fields,
+
+          None => return Err(
+            response!(
+              Wrong(format!("can't take fields out of value of type `{}`", t)),
+              self.source.file,
+              left.pos
+            )
+          ),
+        };
+
+        for name in names {
+          if let Identifier(ref field) = name.node {
+            match fields.iter().find(|f| f.0 == *field) {
+              Some(&(_, ref field_type)) => self.declare(field, field_type.clone(), &name.pos, kind)?,
+
+              None => return Err(
+                response!(
+                  Wrong(format!("no field `{}` on type `{}`", field, t)),
+                  self.source.file,
+                  name.pos
+                )
+              ),
+            }
+          }
+        }
+
+        Ok(())
+      },
+
+      _ => Err(
+        response!(
+          Wrong("unexpected variable declaration"),
+          self.source.file,
+          left.pos
+        )
+      )
+    }
+  }
+
+  fn declare(&mut self, name: &str, t: Type, pos: &TokenElement<'v>, kind: DeclarationKind) -> Result<(), ()> {
+    self.check_declaration(name, &t, pos, kind)?;
+
+    self.scopes.declare(name, t, pos.clone(), kind, kind != DeclarationKind::Function && kind != DeclarationKind::Constant);
+
+    Ok(())
+  }
+
+  // warns about a name declared again in the same scope with another type, or hiding one from an outer scope
+  fn check_declaration(&mut self, name: &str, t: &Type, pos: &TokenElement<'v>, kind: DeclarationKind) -> Result<(), ()> {
+    if kind == DeclarationKind::Parameter || kind == DeclarationKind::Narrowed {
+      return Ok(())
+    }
+
+    let previous = match self.scopes.declared(name) {
+      Some(previous) => previous,
+      None           => return Ok(()),
+    };
+
+    if self.scopes.symbol(previous).scope == self.scopes.current {
+      // declaring it again would be a way around it being constant
+      if self.scopes.symbol(previous).kind == DeclarationKind::Constant {
+        response!(
+          Wrong(format!("can't declare constant `{}` again in the same scope", name)),
+          self.source.file,
+          pos
+        );
+
+        return Err(
+          response!(
+            Note(format!("`{}` is declared here with `::`", name)),
+            self.scopes.symbol(previous).pos
+          )
+        )
+      }
+
+      // the new declaration takes over, so the old one isn't reported as unused as well
+      let previous = self.scopes.symbol_mut(previous);
+
+      previous.used = true;
+
+      if previous.t != *t {
+        self.warnings += 1;
+
+        response!(
+          Weird(format!("`{}` is declared again with type `{}`, it was `{}`", name, t, previous.t)),
+          self.source.file,
+          pos
+        );
+
+        response!(
+          Note(format!("`{}` is first declared here", name)),
+          previous.pos
+        )
+      }
+    } else {
+      self.warnings += 1;
+
+      response!(
+        Weird(format!("`{}` shadows a declaration from an outer scope", name)),
+        self.source.file,
+        pos
+      );
+
+      response!(
+        Note(format!("`{}` is declared here", name)),
+        self.scopes.symbol(previous).pos
+      )
+    }
+
+    Ok(())
+  }
+
+  // tuples are compared element by element, so the error points at the one that's off
+  fn check_type(&mut self, expected: &Type, found: &Type, value: &'v Expression<'v>) -> Result<(), ()> {
+    match (expected, found) {
+      (&Type::Any, _) => Ok(()),
+
+      (expected, found) if found.has_any() && !found.widens_to(expected) && found.consistent_with(expected) => {
+        self.runtime_check(value, expected);
+
+        Ok(())
+      },
+
+      (&Type::Tuple(ref expected_types), &Type::Tuple(ref found_types)) => {
+        if expected_types.len() != found_types.len() {
+          return Err(
+            response!(
+              Wrong(format!("expected a tuple of {} elements, found {}", expected_types.len(), found_types.len())),
+              self.source.file,
+              value.pos
+            )
+          )
+        }
+
+        for (i, (expected, found)) in expected_types.iter().zip(found_types).enumerate() {
+          let element = match value.node {
+            ExpressionNode::Tuple(ref elements) => &elements[i],
+            _                                   => value,
+          };
+
+          self.check_type(expected, found, element)?
+        }
+
+        Ok(())
+      },
+
+      (&Type::List(ref expected_element), &Type::List(_)) if expected != found => match value.node {
+        ExpressionNode::List(ref elements) => {
+          for element in elements {
+            let t = self.type_expression(element)?;
+
+            self.check_type(expected_element, &t, element)?
+          }
+
+          Ok(())
+        },
+
+        _ => Err(
+          response!(
+            Wrong(format!("mismatched types, expected type `{}` got `{}`", expected, found)),
+            self.source.file,
+            value.pos
+          )
+        )
+      },
+
+      (_, &Type::Optional(ref inner)) if expected.optional().is_none() && inner.widens_to(expected) => Err(
+        response!(
+          Wrong(format!("expected `{}`, but this `{}` may be nil, compare it against `nil` first", expected, found)),
+          self.source.file,
+          value.pos
+        )
+      ),
+
+      (_, &Type::Nil) if expected.optional().is_none() => Err(
+        response!(
+          Wrong(format!("`nil` doesn't fit `{}`, only optional types like `{}?` hold it", expected, expected)),
+          self.source.file,
+          value.pos
+        )
+      ),
+
+      _ if Self::out_of_range(expected, &value.node) => Err(
+        response!(
+          Wrong(format!("literal out of range for `{}`", expected)),
+          self.source.file,
+          value.pos
+        )
+      ),
+
+      _ => if !expected.check_expression(&value.node) && !found.widens_to(expected) && !self.implements(found, expected) {
+        Err(
+          response!(
+            Wrong(format!("mismatched types, expected type `{}` got `{}`", expected, found)),
+            self.source.file,
+            value.pos
+          )
+        )
+      } else {
+        Ok(())
+      }
+    }
+  }
+
+
+
+  // a list literal going into `[any]` can mix elements of any type
+  fn type_for(&mut self, value: &'v Expression<'v>, expected: &Type) -> Result<Type, ()> {
+    if let (&ExpressionNode::List(ref elements), &Type::List(ref element)) = (&value.node, expected) {
+      if **element == Type::Any {
+        for element in elements {
+          self.type_expression(element)?;
+        }
+
+        return Ok(expected.clone())
+      }
+    }
+
+    self.type_expression(value)
+  }
+
+  fn runtime_check(&mut self, value: &'v Expression<'v>, t: &Type) {
+    self.checks.push((value.pos.clone(), t.clone()))
+  }
+
+  // number literals that have the right kind but not the room
+  fn out_of_range(expected: &Type, value: &ExpressionNode) -> bool {
+    match *value {
+      ExpressionNode::Int(_)   => expected.integer().is_some() && !expected.check_expression(value),
+      ExpressionNode::Float(_) => expected.float().is_some() && !expected.check_expression(value),
+      _                        => false,
+    }
+  }
+
+
+
+  fn visit_function(&mut self, name: &str, generics: &'v [String], params: &'v [(String, Type)], return_type: &'v Type, body: &'v Expression<'v>, statement: &'v Statement<'v>) -> Result<(), ()> {
+    if Operator::from_str(name).is_some() {
+      return Err(
+        response!(
+          Wrong(format!("operator `{}` can only be defined in an `implement` block", name)),
+          self.source.file,
+          statement.pos
+        )
+      )
+    }
+
+    let outer_generics = self.generics.len();
+
+    self.generics.extend(generics.iter().cloned());
+
+    let t = self.function_type(params, return_type, statement)?;
+
+    // declared up front, so the body can call itself
+    self.declare(name, Self::generalize(generics, t.clone()), &statement.pos, DeclarationKind::Function)?;
+
+    self.visit_body(params, &t, body, statement)?;
+
+    self.generics.truncate(outer_generics);
+
+    Ok(())
+  }
+
+  // `self` is whatever the surrounding trait or `implement` block is for
+  fn function_type(&self, params: &[(String, Type)], return_type: &Type, statement: &'v Statement<'v>) -> Result<Type, ()> {
+    let mut types = Vec::new();
+
+    for &(ref param, ref t) in params {
+      let t = match (param.as_str(), &self.self_type) {
+        ("self", &Some(ref self_type)) if *t == Type::Nil => self_type.clone(),
+
+        ("self", &None) if *t == Type::Nil => return Err(
+          response!(
+            Wrong("`self` only goes in methods of a trait or `implement` block"),
+            self.source.file,
+            statement.pos
+          )
+        ),
+
+        (_, _) if *t == Type::Nil => if self.strict {
+          return Err(
+            response!(
+              Wrong(format!("parameter `{}` needs a type, this file is strict so it can't be `any` implicitly", param)),
+              self.source.file,
+              statement.pos
+            )
+          )
+        } else {
+          Type::Any
+        },
+
+        _ => self.resolve(t),
+      };
+
+      types.push(t)
+    }
+
+    Ok(Type::Fun(types, Rc::new(self.resolve(return_type))))
+  }
+
+  fn visit_body(&mut self, params: &'v [(String, Type)], t: &Type, body: &'v Expression<'v>, statement: &'v Statement<'v>) -> Result<(), ()> {
+    if let Type::Fun(ref types, ref return_type) = *t {
+      self.push_scope();
+
+      for (&(ref param, _), t) in params.iter().zip(types) {
+        self.declare(param, t.clone(), &statement.pos, DeclarationKind::Parameter)?
+      }
+
+      self.return_types.push((**return_type).clone());
+
+      self.visit_block(body)?;
+
+      self.return_types.pop();
+      self.pop_scope();
+
+      if **return_type != Type::Nil && !Self::always_returns(body) {
+        let name = match statement.node {
+          StatementNode::Function(ref name, ..) => name.as_str(),
+          _                                     => "",
+        };
+
+        return Err(
+          response!(
+            Wrong(format!("function `{}` doesn't return on every path, it's declared to return `{}`", name, return_type)),
+            self.source.file,
+            statement.pos
+          )
+        )
+      }
+    }
+
+    Ok(())
+  }
+
+  // methods take `self` first, so they can be called on a value
+  fn method_signature(&self, name: &str, params: &[(String, Type)], statement: &'v Statement<'v>) -> Result<(), ()> {
+    match params.first() {
+      Some(&(ref param, Type::Nil)) if param == "self" => Ok(()),
+
+      _ => Err(
+        response!(
+          Wrong(format!("method `{}` has to take `self` first", name)),
+          self.source.file,
+          statement.pos
+        )
+      ),
+    }
+  }
+
+  // operators are methods taking `self` and the right operand, comparisons give a `bool`
+  fn operator_signature(&self, name: &str, t: &Type, statement: &'v Statement<'v>) -> Result<(), ()> {
+    let op = match Operator::from_str(name) {
+      Some((op, _)) => op,
+      None          => return Ok(()),
+    };
+
+    match *t {
+      Type::Fun(ref params, _) if params.len() != 2 => Err(
+        response!(
+          Wrong(format!("operator `{}` takes `self` and exactly one operand", op)),
+          self.source.file,
+          statement.pos
+        )
+      ),
+
+      Type::Fun(_, ref return_type) if op.is_comparison() && **return_type != Type::Bool => Err(
+        response!(
+          Wrong(format!("operator `{}` has to return `bool`, not `{}`", op, return_type)),
+          self.source.file,
+          statement.pos
+        )
+      ),
+
+      Type::Fun(..) => Ok(()),
+
+      _ => Err(
+        response!(
+          Wrong(format!("operator `{}` can't have type parameters", op)),
+          self.source.file,
+          statement.pos
+        )
+      ),
+    }
+  }
+
+  fn visit_trait(&mut self, name: &str, body: &'v Expression<'v>, statement: &'v Statement<'v>) -> Result<(), ()> {
+    if self.traits.contains_key(name) || self.structs.contains_key(name) {
+      return Err(
+        response!(
+          Wrong(format!("type `{}` is already defined", name)),
+          self.source.file,
+          statement.pos
+        )
+      )
+    }
+
+    let mut signatures: Vec<(String, Type)> = Vec::new();
+
+    self.self_type = Some(Type::Param("Self".to_string()));
+
+    if let ExpressionNode::Block(ref statements) = body.node {
+      for method in statements {
+        match method.node {
+          StatementNode::Signature(ref method_name, ref params, ref return_type) => {
+            if signatures.iter().any(|m| m.0 == *method_name) {
+              return Err(
+                response!(
+                  Wrong(format!("method `{}` is declared twice", method_name)),
+                  self.source.file,
+                  method.pos
+                )
+              )
+            }
+
+            self.method_signature(method_name, params, method)?;
+
+            let t = self.function_type(params, return_type, method)?;
+
+            self.operator_signature(method_name, &t, method)?;
+
+            signatures.push((method_name.clone(), t))
+          },
+
+          _ => return Err(
+            response!(
+              Wrong("expected a method like `fun name(self) -> type`"),
+              self.source.file,
+              method.pos
+            )
+          ),
+        }
+      }
+    }
+
+    self.self_type = None;
+
+    self.traits.insert(name.to_string(), signatures);
+
+    Ok(())
+  }
+
+  // the methods are known before any body is checked, so they can call each other
+  fn visit_implement(&mut self, trait_name: Option<&String>, t: &'v Type, body: &'v Expression<'v>, statement: &'v Statement<'v>) -> Result<(), ()> {
+    let t   = self.resolve(t);
+    let key = t.to_string();
+
+    self.self_type = Some(t.clone());
+
+    let mut methods: Vec<(String, Type)> = Vec::new();
+
+    if let ExpressionNode::Block(ref statements) = body.node {
+      for method in statements {
+        match method.node {
+          StatementNode::Function(ref method_name, ref generics, ref params, ref return_type, _) => {
+            let implemented = self.methods.get(&key).map_or(false, |m| m.iter().any(|m| m.0 == *method_name));
+
+            if implemented || methods.iter().any(|m| m.0 == *method_name) {
+              return Err(
+                response!(
+                  Wrong(format!("method `{}` is already implemented for `{}`", method_name, t)),
+                  self.source.file,
+                  method.pos
+                )
+              )
+            }
+
+            self.method_signature(method_name, params, method)?;
+
+            let outer_generics = self.generics.len();
+
+            self.generics.extend(generics.iter().cloned());
+
+            let method_type = Self::generalize(generics, self.function_type(params, return_type, method)?);
+
+            self.generics.truncate(outer_generics);
+
+            self.operator_signature(method_name, &method_type, method)?;
+
+            methods.push((method_name.clone(), method_type))
+          },
+
+          _ => return Err(
+            response!(
+              Wrong("only methods go in an `implement` block"),
+              self.source.file,
+              method.pos
+            )
+          ),
+        }
+      }
+    }
+
+    if let Some(trait_name) = trait_name {
+      self.check_implementation(trait_name, &t, &methods, statement)?;
+
+      self.implementations.insert((key.clone(), trait_name.clone()));
+    }
+
+    self.methods.entry(key).or_insert_with(Vec::new).extend(methods);
+
+    if let ExpressionNode::Block(ref statements) = body.node {
+      for method in statements {
+        if let StatementNode::Function(_, ref generics, ref params, ref return_type, ref method_body) = method.node {
+          let outer_generics = self.generics.len();
+
+          self.generics.extend(generics.iter().cloned());
+
+          let method_type = self.function_type(params, return_type, method)?;
+
+          self.visit_body(params, &method_type, method_body, method)?;
+
+          self.generics.truncate(outer_generics);
+        }
+      }
+    }
+
+    self.self_type = None;
+
+    Ok(())
+  }
+
+  // every method of the trait is there with the same signature, and nothing else is
+  fn check_implementation(&self, trait_name: &str, t: &Type, methods: &[(String, Type)], statement: &'v Statement<'v>) -> Result<(), ()> {
+    let signatures = match self.traits.get(trait_name) {
+      Some(signatures) => signatures,
+
+      None => return Err(
+        response!(
+          Wrong(format!("no such trait `{}`", trait_name)),
+          self.source.file,
+          statement.pos
+        )
+      ),
+    };
+
+    if self.implementations.contains(&(t.to_string(), trait_name.to_string())) {
+      return Err(
+        response!(
+          Wrong(format!("`{}` is already implemented for `{}`", trait_name, t)),
+          self.source.file,
+          statement.pos
+        )
+      )
+    }
+
+    let mut arguments = HashMap::new();
+
+    arguments.insert("Self".to_string(), t.clone());
+
+    for &(ref name, ref signature) in signatures {
+      let expected = signature.substitute(&arguments);
+
+      match methods.iter().find(|m| m.0 == *name) {
+        Some(&(_, ref found)) if *found == expected => (),
+
+        Some(&(_, ref found)) => return Err(
+          response!(
+            Wrong(format!("method `{}` doesn't match `{}`, expected `{}` got `{}`", name, trait_name, expected, found)),
+            self.source.file,
+            statement.pos
+          )
+        ),
+
+        None => return Err(
+          response!(
+            Wrong(format!("`{}` is missing method `{}` of `{}`, `{}`", t, name, trait_name, expected)),
+            self.source.file,
+            statement.pos
+          )
+        ),
+      }
+    }
+
+    if let Some(&(ref name, _)) = methods.iter().find(|m| !signatures.iter().any(|s| s.0 == m.0)) {
+      return Err(
+        response!(
+          Wrong(format!("method `{}` isn't part of `{}`", name, trait_name)),
+          self.source.file,
+          statement.pos
+        )
+      )
+    }
+
+    Ok(())
+  }
+
+  // a method as seen through a value, without its `self`
+  fn method_of(&self, t: &Type, name: &str) -> Option<Type> {
+    let method = match *t {
+      Type::Id(ref id) if self.traits.contains_key(id) => {
+        let mut arguments = HashMap::new();
+
+        arguments.insert("Self".to_string(), t.clone());
+
+        self.traits[id].iter().find(|m| m.0 == name).map(|m| m.1.substitute(&arguments))
+      },
+
+      _ => self.methods.get(&t.to_string()).and_then(|methods| methods.iter().find(|m| m.0 == name).map(|m| m.1.clone())),
+    };
+
+    match method {
+      Some(Type::Fun(params, return_type)) => Some(Type::Fun(params[1 ..].to_vec(), return_type)),
+
+      Some(Type::Generic(generics, t)) => match *t {
+        Type::Fun(ref params, ref return_type) => Some(Type::Generic(generics.clone(), Rc::new(Type::Fun(params[1 ..].to_vec(), return_type.clone())))),
+        _                                      => None,
+      },
+
+      _ => None,
+    }
+  }
+
+  // the type of an operator implemented for the left operand's type, `!=` falls back on `==`
+  fn overloaded(&self, left: &Type, op: &Operator, right: &Type, right_value: &'v Expression<'v>) -> Option<Type> {
+    let method = self.method_of(left, op.as_str()).or_else(|| if *op == Operator::NEq {
+      self.method_of(left, Operator::Eq.as_str())
+    } else {
+      None
+    })?;
+
+    match method {
+      Type::Fun(ref params, ref return_type) if params.len() == 1 => {
+        let param = &params[0];
+
+        if right.widens_to(param) || param.check_expression(&right_value.node) || self.implements(right, param) {
+          Some((**return_type).clone())
+        } else {
+          None
+        }
+      },
+
+      _ => None,
+    }
+  }
+
+  // a value of a type that implements the trait fits where the trait is expected
+  fn implements(&self, t: &Type, trait_type: &Type) -> bool {
+    match *trait_type {
+      Type::Id(ref trait_name) if self.traits.contains_key(trait_name) => self.implementations.contains(&(t.to_string(), trait_name.clone())),
+      _                                                                  => false,
+    }
+  }
+
+  // fields are declarations without values, the name doubles as a constructor taking them in order
+  fn visit_struct(&mut self, name: &str, generics: &'v [String], body: &'v Expression<'v>, statement: &'v Statement<'v>) -> Result<(), ()> {
+    let mut fields: Vec<(String, Type)> = Vec::new();
+
+    let outer_generics = self.generics.len();
+
+    self.generics.extend(generics.iter().cloned());
+
+    if let ExpressionNode::Block(ref statements) = body.node {
+      for field in statements {
+        match field.node {
+          StatementNode::Variable(ref t, Expression { node: ExpressionNode::Identifier(ref field_name), .. }, None) => {
+            if fields.iter().any(|f| f.0 == *field_name) {
+              return Err(
+                response!(
+                  Wrong(format!("field `{}` is declared twice", field_name)),
+                  self.source.file,
+                  field.pos
+                )
+              )
+            }
+
+            fields.push((field_name.clone(), self.resolve(t)))
+          },
+
+          _ => return Err(
+            response!(
+              Wrong("expected a field like `name: type`"),
+              self.source.file,
+              field.pos
+            )
+          ),
+        }
+      }
+    }
+
+    self.generics.truncate(outer_generics);
+
+    if self.structs.contains_key(name) || self.traits.contains_key(name) {
+      return Err(
+        response!(
+          Wrong(format!("type `{}` is already defined", name)),
+          self.source.file,
+          statement.pos
+        )
+      )
+    }
+
+    let instance = if generics.is_empty() {
+      Type::Id(name.to_string())
+    } else {
+      Type::Instance(name.to_string(), generics.iter().map(|g| Type::Param(g.clone())).collect())
+    };
+
+    let constructor = Self::generalize(generics, Type::Fun(fields.iter().map(|f| f.1.clone()).collect(), Rc::new(instance)));
+
+    self.structs.insert(name.to_string(), (generics.to_vec(), fields));
+
+    self.declare(name, constructor, &statement.pos, DeclarationKind::Function)
+  }
+
+  fn visit_return(&mut self, value: Option<&'v Expression<'v>>, statement: &'v Statement<'v>) -> Result<(), ()> {
+    let expected = match self.return_types.last() {
+      Some(t) => t.clone(),
+      None    => return Err(
+        response!(
+          Wrong("`return` outside of a function"),
+          self.source.file,
+          statement.pos
+        )
+      )
+    };
+
+    match value {
+      Some(value) => {
+        let t = self.type_for(value, &expected)?;
+
+        self.check_type(&expected, &t, value)
+      },
+
+      None => if expected != Type::Nil {
+        Err(
+          response!(
+            Wrong(format!("expected return value of type `{}`", expected)),
+            self.source.file,
+            statement.pos
+          )
+        )
+      } else {
+        Ok(())
+      }
+    }
+  }
+
+
+
+  fn visit_for(&mut self, binding: &'v Expression<'v>, iterable: &'v Expression<'v>, body: &'v Expression<'v>) -> Result<(), ()> {
+    let element = match self.type_expression(iterable)? {
+      Type::Range(t) | Type::List(t) => (*t).clone(),
+      Type::Any                      => Type::Any,
+
+      t => return Err(
+        response!(
+          Wrong(format!("can't iterate over value of type `{}`", t)),
+          self.source.file,
+          iterable.pos
+        )
+      )
+    };
+
+    self.push_scope();
+
+    if let ExpressionNode::Identifier(ref name) = binding.node {
+      self.declare(name, element, &binding.pos, DeclarationKind::Variable)?
+    }
+
+    self.visit_block(body)?;
+
+    self.pop_scope();
+
+    Ok(())
+  }
+
+  // visits the statements of a block in the current scope
+  fn visit_block(&mut self, block: &'v Expression<'v>) -> Result<(), ()> {
+    if let ExpressionNode::Block(ref statements) = block.node {
+      self.check_reachable(statements);
+
+      for statement in statements {
+        self.visit_statement(statement)?
+      }
+    }
+
+    Ok(())
+  }
+
+
+
+  pub fn type_expression(&mut self, expression: &'v Expression<'v>) -> Result<Type, ()> {
+    let t = self.type_node(expression)?;
+
+    self.types.insert(expression, t.clone());
+
+    Ok(t)
+  }
+
+  fn type_node(&mut self, expression: &'v Expression<'v>) -> Result<Type, ()> {
+    use self::ExpressionNode::*;
+
+    let t = match expression.node {
+      Identifier(ref name) => if let Some(id) = self.scopes.lookup(name) {
+        let symbol = self.scopes.symbol_mut(id);
+
+        symbol.used = true;
+        symbol.t.clone()
+      } else {
+        return Err(
+          response!(
+            Wrong(format!("no such value `{}` in this scope", name)),
+            self.source.file,
+            expression.pos
+          )
+        )
+      },
+
+      String(_) => Type::String,
+      Char(_)   => Type::Char,
+      Bool(_)   => Type::Bool,
+      Nil       => Type::Nil,
+      Int(_)    => Type::Int,
+      Float(_)  => Type::Float,
+
+      Block(_) => self.type_block(expression, true)?,
+      If(..)   => self.type_if(expression, true)?,
+
+      Binary(ref left, ref op, ref right) => {
+        let left_type  = self.type_expression(left)?;
+        let right_type = self.type_expression(right)?;
+
+        // a literal takes the type of the other side when it fits, so `x + 1` stays a `u8`
+        let left_type  = Self::literal_type(left, left_type, &right_type);
+        let right_type = Self::literal_type(right, right_type, &left_type);
+
+        if let Some(t) = self.overloaded(&left_type, op, &right_type, right) {
+          return Ok(t)
+        }
+
+        match left_type.binary(op, &right_type) {
+          Some(t) => t,
+          None    => {
+            let message = match (self.method_of(&left_type, op.as_str()), &left_type) {
+              (Some(Type::Fun(ref params, _)), _) => format!("`{}` on `{}` takes `{}`, not `{}`", op, left_type, params[0], right_type),
+
+              (None, &Type::Id(_)) | (None, &Type::Instance(..)) => format!(
+                "no implementation of `{}` for `{}`, add `fun {}(self, other: {})` to an `implement {}` block",
+                op, left_type, op, right_type, left_type
+              ),
+
+              _ => format!("no operator `{}` for `{}` and `{}`", op, left_type, right_type),
+            };
+
+            response!(
+              Wrong(message),
+              self.source.file,
+              Self::operator_position(left, right)
+            );
+
+            response!(Note(Self::operand_note("left", &left_type)), left.pos);
+
+            return Err(
+              response!(Note(Self::operand_note("right", &right_type)), right.pos)
+            )
+          },
+        }
+      },
+
+      Range(ref start, ref end, _, ref step) => {
+        let mut t = self.type_bound(start)?;
+
+        for bound in Some(end).into_iter().chain(step.as_ref()) {
+          let bound_type = Self::literal_type(bound, self.type_bound(bound)?, &t);
+
+          t = match Self::literal_type(start, t.clone(), &bound_type).unify(&bound_type) {
+            Some(t) => t,
+            None    => return Err(
+              response!(
+                Wrong(format!("range bounds `{}` and `{}` don't mix", t, bound_type)),
+                self.source.file,
+                bound.pos
+              )
+            )
+          }
+        }
+
+        Type::Range(Rc::new(t))
+      },
+
+      Tuple(ref elements) => {
+        let mut types = Vec::new();
+
+        for element in elements {
+          types.push(self.type_expression(element)?)
+        }
+
+        Type::Tuple(types)
+      },
+
+      Call(ref callee, ref arguments) => self.type_call(expression, callee, arguments)?,
+
+      Unary(ref op, ref operand) => {
+        let t = self.type_expression(operand)?;
+
+        match t.integer() {
+          Some((false, _)) => return Err(
+            response!(
+              Wrong(format!("can't apply unary `{}` to unsigned `{}`", op, t)),
+              self.source.file,
+              expression.pos
+            )
+          ),
+
+          _ if t.is_numeric() || t == Type::Any => t,
+
+          _ => return Err(
+            response!(
+              Wrong(format!("can't apply unary `{}` to `{}`", op, t)),
+              self.source.file,
+              expression.pos
+            )
+          ),
+        }
+      },
+
+      Field(ref value, ref name) => self.type_field(value, name, expression)?,
+
+      Cast(ref value, ref t) => {
+        let t          = &self.resolve(t);
+        let value_type = self.type_expression(value)?;
+
+        if !value_type.casts_to(t) {
+          return Err(
+            response!(
+              Wrong(format!("can't cast `{}` as `{}`", value_type, t)),
+              self.source.file,
+              expression.pos
+            )
+          )
+        }
+
+        if value_type == Type::Any && *t != Type::Any {
+          self.runtime_check(value, t)
+        }
+
+        t.clone()
+      },
+
+      List(ref elements) => {
+        let mut t = Type::Nil;
+
+        for (i, element) in elements.iter().enumerate() {
+          let element_type = self.type_expression(element)?;
+
+          t = if i == 0 {
+            element_type
+          } else {
+            match t.unify(&element_type) {
+              Some(t) => t,
+              None    => return Err(
+                response!(
+                  Wrong(format!("mismatched list elements, expected `{}` got `{}`", t, element_type)),
+                  self.source.file,
+                  element.pos
+                )
+              )
+            }
+          }
+        }
+
+        Type::List(Rc::new(t))
+      },
+
+      Fields(_) => return Err(
+        response!(
+          Wrong("field patterns only go on the left of a declaration"),
+          self.source.file,
+          expression.pos
+        )
+      ),
+
+      _ => Type::Nil,
+    };
+
+    Ok(t)
+  }
+
+  // a block has the value of its last expression, `used` is false when the value is thrown away
+  fn type_block(&mut self, block: &'v Expression<'v>, used: bool) -> Result<Type, ()> {
+    let mut t = Type::Nil;
+
+    if let ExpressionNode::Block(ref statements) = block.node {
+      self.check_reachable(statements);
+      self.push_scope();
+
+      for (i, statement) in statements.iter().enumerate() {
+        match statement.node {
+          StatementNode::Expression(ref expression) if used && i == statements.len() - 1 => {
+            t = self.type_expression(expression)?
+          },
+
+          _ => self.visit_statement(statement)?,
+        }
+      }
+
+      self.pop_scope();
+    }
+
+    Ok(t)
+  }
+
+  fn type_if(&mut self, expression: &'v Expression<'v>, used: bool) -> Result<Type, ()> {
+    if let ExpressionNode::If(ref condition, ref body, ref otherwise) = expression.node {
+      let condition_type = self.type_expression(condition)?;
+
+      if condition_type == Type::Any {
+        self.runtime_check(condition, &Type::Bool)
+      } else if condition_type != Type::Bool {
+        return Err(
+          response!(
+            Wrong(format!("expected condition of type `bool` got `{}`", condition_type)),
+            self.source.file,
+            condition.pos
+          )
+        )
+      }
+
+      let check = self.nil_check(condition)?;
+
+      let narrowed  = self.push_narrowed(&check, true, &condition.pos)?;
+      let body_type = self.type_block(body, used)?;
+
+      if narrowed {
+        self.pop_scope()
+      }
+
+      let narrowed       = self.push_narrowed(&check, false, &condition.pos)?;
+      let otherwise_type = match *otherwise {
+        Some(ref otherwise) => match otherwise.node {
+          ExpressionNode::If(..) => self.type_if(otherwise, used)?,
+          _                      => self.type_block(otherwise, used)?,
+        },
+
+        None => Type::Nil,
+      };
+
+      if narrowed {
+        self.pop_scope()
+      }
+
+      // `if x == nil { return }` leaves `x` non-nil for the rest of the block
+      if let Some((ref name, ref t, false)) = check {
+        if otherwise.is_none() && Self::always_returns(body) {
+          self.declare(name, t.clone(), &condition.pos, DeclarationKind::Narrowed)?
+        }
+      }
+
+      if !used {
+        return Ok(Type::Nil)
+      }
+
+      match body_type.unify(&otherwise_type) {
+        Some(t) => Ok(t),
+        None    => Err(
+          response!(
+            Wrong(format!("`if` and `else` have incompatible types `{}` and `{}`", body_type, otherwise_type)),
+            self.source.file,
+            expression.pos
+          )
+        )
+      }
+    } else {
+      unreachable!()
+    }
+  }
+
+  // `x != nil` or `x == nil` on an optional `x`, giving its name, the type it narrows to,
+  // and whether that holds when the condition is true
+  fn nil_check(&mut self, condition: &'v Expression<'v>) -> Result<Option<(String, Type, bool)>, ()> {
+    use self::ExpressionNode::*;
+
+    if let Binary(ref left, ref op, ref right) = condition.node {
+      let (value, name) = match (&left.node, &right.node) {
+        (&Identifier(ref name), &Nil) => (left, name),
+        (&Nil, &Identifier(ref name)) => (right, name),
+        _                             => return Ok(None),
+      };
+
+      let non_nil = match *op {
+        Operator::NEq => true,
+        Operator::Eq  => false,
+        _             => return Ok(None),
+      };
+
+      if let Type::Optional(t) = self.type_expression(value)? {
+        return Ok(Some((name.clone(), (*t).clone(), non_nil)))
+      }
+    }
+
+    Ok(None)
+  }
+
+  // shadows the checked name with its non-nil type in a scope of its own, for the branch where it isn't nil
+  fn push_narrowed(&mut self, check: &Option<(String, Type, bool)>, branch: bool, pos: &TokenElement<'v>) -> Result<bool, ()> {
+    match *check {
+      Some((ref name, ref t, non_nil)) if non_nil == branch => {
+        self.push_scope();
+        self.declare(name, t.clone(), pos, DeclarationKind::Narrowed)?;
+
+        Ok(true)
+      },
+
+      _ => Ok(false),
+    }
+  }
+
+  // whether running the expression never gets past it, a block with a `return` in it or an `if` returning either way
+  pub fn always_returns(expression: &Expression) -> bool {
+    match expression.node {
+      ExpressionNode::Block(ref statements) => statements.iter().any(Self::diverges),
+
+      ExpressionNode::If(_, ref body, Some(ref otherwise)) => Self::always_returns(body) && Self::always_returns(otherwise),
+
+      _ => false,
+    }
+  }
+
+  pub fn diverges(statement: &Statement) -> bool {
+    match statement.node {
+      StatementNode::Return(_)              => true,
+      StatementNode::Expression(ref inside) => Self::always_returns(inside),
+      _                                     => false,
+    }
+  }
+
+  // warns about the first statement after one that never finishes, the rest of the block goes with it
+  fn check_reachable(&mut self, statements: &'v [Statement<'v>]) {
+    if let Some(i) = statements.iter().position(Self::diverges) {
+      if let Some(unreachable) = statements.get(i + 1) {
+        self.warnings += 1;
+
+        response!(
+          Weird("unreachable code"),
+          self.source.file,
+          unreachable.pos
+        );
+
+        response!(
+          Note("any code after this never runs"),
+          statements[i].pos
+        );
+      }
+    }
+  }
+
+  fn type_field(&mut self, value: &'v Expression<'v>, name: &str, field: &'v Expression<'v>) -> Result<Type, ()> {
+    let t = self.type_expression(value)?;
+
+    if t == Type::Any {
+      return Ok(t)
+    }
+
+    if let Some(fields) = self.fields_of(&t) {
+      if let Some(&(_, ref field_type)) = fields.iter().find(|f| f.0 == name) {
+        return Ok(field_type.clone())
+      }
+    }
+
+    if let Some(method) = self.method_of(&t, name) {
+      return Ok(method)
+    }
+
+    let message = match t {
+      Type::Optional(_) => format!("value of type `{}` may be nil, compare it against `nil` before using `.{}`", t, name),
+      _                 => format!("no field `{}` on type `{}`", name, t),
+    };
+
+    Err(
+      response!(
+        Wrong(message),
+        self.source.file,
+        field.pos
+      )
+    )
+  }
+
+  fn type_call(&mut self, call: &'v Expression<'v>, callee: &'v Expression<'v>, arguments: &'v [Expression<'v>]) -> Result<Type, ()> {
+    let callee_type = self.type_expression(callee)?;
+
+    let (generics, t) = match callee_type {
+      Type::Generic(ref generics, ref t) => (generics.clone(), (**t).clone()),
+      ref t                              => (Vec::new(), t.clone()),
+    };
+
+    let (mut params, mut return_type) = match t {
+      Type::Fun(params, return_type) => (params, return_type),
+
+      Type::Any => {
+        for argument in arguments {
+          self.type_expression(argument)?;
+        }
+
+        return Ok(Type::Any)
+      },
+
+      t => return Err(
+        response!(
+          Wrong(format!("can't call value of type `{}`", t)),
+          self.source.file,
+          callee.pos
+        )
+      )
+    };
+
+    if params.len() != arguments.len() {
+      return Err(
+        response!(
+          Wrong(format!("expected {} argument{}, found {}", params.len(), if params.len() == 1 { "" } else { "s" }, arguments.len())),
+          self.source.file,
+          call.pos
+        )
+      )
+    }
+
+    let mut types = Vec::new();
+
+    for argument in arguments {
+      types.push(self.type_expression(argument)?)
+    }
+
+    // the type arguments come from the arguments, each parameter has to be pinned down by at least one
+    if !generics.is_empty() {
+      let mut inferred = HashMap::new();
+
+      for ((param, t), argument) in params.iter().zip(&types).zip(arguments) {
+        if let Err((generic, a, b)) = param.infer(t, &mut inferred) {
+          return Err(
+            response!(
+              Wrong(format!("type argument `{}` can't be both `{}` and `{}`", generic, a, b)),
+              self.source.file,
+              argument.pos
+            )
+          )
+        }
+      }
+
+      if let Some(generic) = generics.iter().find(|g| !inferred.contains_key(*g)) {
+        response!(
+          Wrong(format!("can't infer type argument `{}` from the arguments of this call", generic)),
+          self.source.file,
+          call.pos
+        );
+
+        return Err(
+          response!(Note(format!("this is `{}`, its arguments have to pin down `{}`", callee_type, generic)), callee.pos)
+        )
+      }
+
+      params      = params.iter().map(|p| p.substitute(&inferred)).collect();
+      return_type = Rc::new(return_type.substitute(&inferred));
+    }
+
+    for ((param, t), argument) in params.iter().zip(&types).zip(arguments) {
+      self.check_type(param, t, argument)?
+    }
+
+    Ok((*return_type).clone())
+  }
+
+  fn operand_note(side: &str, t: &Type) -> String {
+    match t.optional() {
+      Some(_) => format!("{} operand is `{}`, which may be nil", side, t),
+      None    => format!("{} operand is `{}`", side, t),
+    }
+  }
+
+  fn literal_type(value: &Expression<'v>, t: Type, other: &Type) -> Type {
+    match value.node {
+      ExpressionNode::Int(_) | ExpressionNode::Float(_) if other.is_numeric() && other.check_expression(&value.node) => other.clone(),
+      _                                                                                                         => t,
+    }
+  }
+
+  // the operator sits between its operands, past any whitespace and parentheses
+  fn operator_position(left: &Expression<'v>, right: &Expression<'v>) -> TokenElement<'v> {
+    if let (&TokenElement::Pos(line, (_, end)), &TokenElement::Pos(right_line, (start, _))) = (&left.pos, &right.pos) {
+      if line.0 == right_line.0 && end < start {
+        let between = &line.1[end .. start - 1];
+        let trim    = |c: char| c.is_whitespace() || c == '(' || c == ')';
+        let offset  = between.len() - between.trim_start_matches(trim).len();
+
+        return TokenElement::Pos(line, (end + offset + 1, end + between.trim_end_matches(trim).len()))
+      }
+    }
+
+    left.pos.clone()
+  }
+
+  // range bounds are numbers, mixing them makes a range of the wider type
+  fn type_bound(&mut self, bound: &'v Expression<'v>) -> Result<Type, ()> {
+    match self.type_expression(bound)? {
+      ref t if t.is_numeric() => Ok(t.clone()),
+
+      t => Err(
+        response!(
+          Wrong(format!("range bounds must be numbers, found `{}`", t)),
+          self.source.file,
+          bound.pos
+        )
+      )
+    }
+  }
+
+
+
+  pub fn push_scope(&mut self) {
+    self.scopes.push();
+  }
+
+  pub fn pop_scope(&mut self) {
+    let scope = self.scopes.pop();
+
+    for &id in &self.scopes.scopes[scope].symbols {
+      let symbol = &self.scopes.symbols[id];
+
+      if symbol.kind == DeclarationKind::Variable && !symbol.used && !symbol.name.starts_with('_') {
+        self.warnings += 1;
+
+        response!(
+          Weird(format!("`{}` is never used", symbol.name)),
+          self.source.file,
+          symbol.pos
+        )
+      }
+    }
+  }
+}
@@ -10,16 +10,27 @@ mod lait;
 use lait::lexer::*;
 use lait::parser::*;
 use lait::visitor::*;
+use lait::formatter::*;
+use lait::lint::*;
 
 use lait::source::Source;
 
-fn main() {
-  let content = r"
-foo: int = false
-  ";
+use std::env;
+use std::fs;
+use std::process;
+
+fn load(path: &str) -> Option<String> {
+  match fs::read_to_string(path) {
+    Ok(content) => Some(content),
+    Err(error)  => {
+      println!("{}: {}", path, error);
+      None
+    }
+  }
+}
 
-  let source = Source::from("main.rs/testing.wu", content.lines().map(|x| x.into()).collect::<Vec<String>>());
-  let lexer  = Lexer::default(content.chars().collect(), &source);
+fn tokenize<'t>(content: &str, source: &'t Source) -> Option<Vec<Token<'t>>> {
+  let lexer = Lexer::lossless(content.chars().collect(), source);
 
   let mut tokens = Vec::new();
 
@@ -27,22 +38,146 @@ foo: int = false
     if let Ok(token) = token_result {
       tokens.push(token)
     } else {
-      return
+      return None
     }
   }
 
-  let tokens_ref = tokens.iter().map(|x| &*x).collect::<Vec<&Token>>();
+  Some(tokens)
+}
+
+// lexes and parses a file's content, handing its source, syntax tree and ast to `f`,
+// `None` when it doesn't get that far
+fn parse<T, F: for<'p> FnOnce(&'p Source, &'p SyntaxNode<'p>, Vec<Statement<'p>>) -> T>(path: &str, content: &str, f: F) -> Option<T> {
+  let source = Source::from(path, content.lines().map(|x| x.into()).collect::<Vec<String>>());
+  let syntax = SyntaxNode::build(&tokenize(content, &source)?);
+
+  let ast = match Parser::new(syntax.significant(), &source).parse() {
+    Ok(ast) => ast,
+    _       => return None,
+  };
+
+  Some(f(&source, &syntax, ast))
+}
+
+pub enum Emit {
+  Nothing,
+  Json,
+  Sexp,
+  Checks, // the runtime checks left for `any` values, once the file is checked
+}
+
+fn run(path: &str, emit: Emit) -> bool {
+  let content = match load(path) {
+    Some(content) => content,
+    None          => return false,
+  };
+
+  parse(path, &content, |source, syntax, ast| check(source, syntax, ast, &emit)).unwrap_or(false)
+}
+
+fn check<'c>(source: &'c Source, syntax: &'c SyntaxNode<'c>, ast: Vec<Statement<'c>>, emit: &Emit) -> bool {
+  match *emit {
+    Emit::Json                   => println!("{}", ast.to_json()),
+    Emit::Sexp                   => println!("{}", ast.to_sexp()),
+    Emit::Checks | Emit::Nothing => (),
+  }
+
+  let ast = match ConstantFolder::new(source).fold_statements(ast) {
+    Ok(ast) => ast,
+    _       => return false,
+  };
 
-  let mut parser  = Parser::new(tokens_ref, &source);
+  let directives  = syntax.directives();
+  let mut visitor = Visitor::new(source, &ast, &directives);
 
-  match parser.parse() {
-    Ok(ast) => {
-      println!("{:#?}", ast);
+  if visitor.visit().is_err() {
+    return false
+  }
 
-      let mut visitor = Visitor::new(&source, &ast);      
- 
-      visitor.visit();
+  if let Emit::Checks = *emit {
+    println!("{}", checks_to_json(&visitor.checks))
+  }
+
+  if DefiniteAssignment::new(source, &visitor.aliases).walk_statements(&ast).is_err() {
+    return false
+  }
+
+  let config = match LintConfig::load(source, &directives) {
+    Ok(config) => config,
+    _          => return false,
+  };
+
+  Linter::new(source, &config, &visitor.types).lint(&ast)
+}
+
+// formats in place, or only reports files that would change when checking
+fn format(path: &str, check: bool) -> bool {
+  let content = match load(path) {
+    Some(content) => content,
+    None          => return false,
+  };
+
+  // the tree's own text is the file as it is
+  let (formatted, text) = match parse(path, &content, |_, syntax, ast| (Formatter::new(syntax).format(&ast), syntax.text())) {
+    Some(result) => result,
+    None         => return false,
+  };
+
+  if formatted == text {
+    true
+  } else if check {
+    println!("{} is not formatted", path);
+    false
+  } else {
+    match fs::write(path, formatted) {
+      Ok(_)      => true,
+      Err(error) => {
+        println!("{}: {}", path, error);
+        false
+      }
+    }
+  }
+}
+
+fn main() {
+  let args = env::args().skip(1).collect::<Vec<String>>();
+
+  let success = match args.first().map(|x| x.as_str()) {
+    Some("fmt") => {
+      let check = args.iter().any(|x| x == "--check");
+      let paths = args[1 ..].iter().filter(|x| *x != "--check").collect::<Vec<&String>>();
+
+      if paths.is_empty() {
+        println!("usage: lait fmt [--check] <file>...");
+        process::exit(2)
+      }
+
+      paths.iter().fold(true, |success, path| format(path, check) && success)
+    },
+
+    Some(path) => {
+      let emit = match args.iter().position(|x| x == "--emit").and_then(|i| args.get(i + 1)).map(|x| x.as_str()) {
+        Some("json")   => Emit::Json,
+        Some("sexp")   => Emit::Sexp,
+        Some("checks") => Emit::Checks,
+        None           => Emit::Nothing,
+
+        Some(other) => {
+          println!("unknown emit format `{}`, expected `json`, `sexp` or `checks`", other);
+          process::exit(2)
+        }
+      };
+
+      run(path, emit)
     },
-    _ => ()
+
+    None => {
+      println!("usage: lait <file> [--emit json|sexp|checks]\n       lait fmt [--check] <file>...");
+      process::exit(2)
+    }
+  };
+
+  if !success {
+    process::exit(1)
   }
 }